@@ -9,6 +9,8 @@ pub struct CurriculumExercise {
     pub keys: Vec<String>,
     pub tempo_range: [f64; 2],
     pub midi_range: [i32; 2],
+    /// Names of exercises that must be completed before this one unlocks.
+    pub prerequisites: Vec<String>,
 }
 
 #[derive(Serialize, Clone, Debug)]
@@ -35,6 +37,7 @@ pub fn get_curriculum() -> Vec<CurriculumStage> {
                     keys: vec!["C4".to_string()],
                     tempo_range: [60.0, 80.0],
                     midi_range: [60, 67], // C4-G4
+                    prerequisites: Vec::new(),
                 },
                 CurriculumExercise {
                     exercise_type: "major_scale".to_string(),
@@ -44,6 +47,7 @@ pub fn get_curriculum() -> Vec<CurriculumStage> {
                     keys: vec!["C4".to_string()],
                     tempo_range: [60.0, 80.0],
                     midi_range: [60, 67],
+                    prerequisites: vec!["Long Tones".to_string()],
                 },
             ],
         },
@@ -60,6 +64,7 @@ pub fn get_curriculum() -> Vec<CurriculumStage> {
                     keys: vec!["C4".to_string(), "F4".to_string(), "G4".to_string()],
                     tempo_range: [70.0, 90.0],
                     midi_range: [60, 72], // C4-C5
+                    prerequisites: vec!["C Major Scale".to_string()],
                 },
                 CurriculumExercise {
                     exercise_type: "lip_slurs".to_string(),
@@ -69,6 +74,7 @@ pub fn get_curriculum() -> Vec<CurriculumStage> {
                     keys: vec!["C4".to_string()],
                     tempo_range: [70.0, 90.0],
                     midi_range: [60, 72],
+                    prerequisites: vec!["Long Tones".to_string()],
                 },
                 CurriculumExercise {
                     exercise_type: "chromatic".to_string(),
@@ -78,6 +84,7 @@ pub fn get_curriculum() -> Vec<CurriculumStage> {
                     keys: vec!["C4".to_string()],
                     tempo_range: [70.0, 90.0],
                     midi_range: [60, 72],
+                    prerequisites: vec!["C Major Scale".to_string()],
                 },
                 CurriculumExercise {
                     exercise_type: "long_tones".to_string(),
@@ -87,6 +94,7 @@ pub fn get_curriculum() -> Vec<CurriculumStage> {
                     keys: vec!["C4".to_string()],
                     tempo_range: [60.0, 80.0],
                     midi_range: [60, 72],
+                    prerequisites: vec!["Long Tones".to_string()],
                 },
             ],
         },
@@ -116,6 +124,7 @@ pub fn get_curriculum() -> Vec<CurriculumStage> {
                     ],
                     tempo_range: [80.0, 120.0],
                     midi_range: [60, 79], // C4-G5
+                    prerequisites: vec!["Scales in C, F, G".to_string()],
                 },
                 CurriculumExercise {
                     exercise_type: "intervals".to_string(),
@@ -125,6 +134,10 @@ pub fn get_curriculum() -> Vec<CurriculumStage> {
                     keys: vec!["C4".to_string(), "F4".to_string(), "G4".to_string()],
                     tempo_range: [80.0, 120.0],
                     midi_range: [60, 79],
+                    prerequisites: vec![
+                        "Scales in C, F, G".to_string(),
+                        "Simple Lip Slurs".to_string(),
+                    ],
                 },
                 CurriculumExercise {
                     exercise_type: "arpeggios".to_string(),
@@ -134,6 +147,7 @@ pub fn get_curriculum() -> Vec<CurriculumStage> {
                     keys: vec!["C4".to_string(), "F4".to_string(), "G4".to_string()],
                     tempo_range: [80.0, 120.0],
                     midi_range: [60, 79],
+                    prerequisites: vec!["Scales in C, F, G".to_string()],
                 },
                 CurriculumExercise {
                     exercise_type: "lip_slurs".to_string(),
@@ -143,6 +157,10 @@ pub fn get_curriculum() -> Vec<CurriculumStage> {
                     keys: vec!["C4".to_string(), "F4".to_string()],
                     tempo_range: [80.0, 110.0],
                     midi_range: [60, 79],
+                    prerequisites: vec![
+                        "Simple Lip Slurs".to_string(),
+                        "Extended Long Tones".to_string(),
+                    ],
                 },
                 CurriculumExercise {
                     exercise_type: "broken_thirds".to_string(),
@@ -152,6 +170,7 @@ pub fn get_curriculum() -> Vec<CurriculumStage> {
                     keys: vec!["C4".to_string(), "F4".to_string(), "G4".to_string()],
                     tempo_range: [80.0, 110.0],
                     midi_range: [60, 79],
+                    prerequisites: vec!["Scales in C, F, G".to_string()],
                 },
             ],
         },
@@ -169,6 +188,7 @@ pub fn get_curriculum() -> Vec<CurriculumStage> {
                     keys: vec!["C4".to_string(), "G4".to_string(), "C5".to_string()],
                     tempo_range: [100.0, 160.0],
                     midi_range: [60, 84], // C4-C6
+                    prerequisites: vec!["Scales in All Keys".to_string()],
                 },
                 CurriculumExercise {
                     exercise_type: "octave_studies".to_string(),
@@ -178,6 +198,10 @@ pub fn get_curriculum() -> Vec<CurriculumStage> {
                     keys: vec!["C4".to_string(), "F4".to_string(), "G4".to_string()],
                     tempo_range: [100.0, 140.0],
                     midi_range: [60, 84],
+                    prerequisites: vec![
+                        "Interval Training".to_string(),
+                        "Advanced Lip Slurs".to_string(),
+                    ],
                 },
                 CurriculumExercise {
                     exercise_type: "broken_thirds".to_string(),
@@ -195,6 +219,7 @@ pub fn get_curriculum() -> Vec<CurriculumStage> {
                     ],
                     tempo_range: [110.0, 160.0],
                     midi_range: [60, 84],
+                    prerequisites: vec!["Broken Thirds".to_string()],
                 },
                 CurriculumExercise {
                     exercise_type: "chromatic".to_string(),
@@ -204,6 +229,10 @@ pub fn get_curriculum() -> Vec<CurriculumStage> {
                     keys: vec!["C4".to_string()],
                     tempo_range: [100.0, 150.0],
                     midi_range: [60, 84],
+                    prerequisites: vec![
+                        "Chromatic Scale".to_string(),
+                        "Scales in All Keys".to_string(),
+                    ],
                 },
                 CurriculumExercise {
                     exercise_type: "arpeggios".to_string(),
@@ -221,12 +250,26 @@ pub fn get_curriculum() -> Vec<CurriculumStage> {
                     ],
                     tempo_range: [100.0, 140.0],
                     midi_range: [60, 84],
+                    prerequisites: vec!["Arpeggios".to_string()],
                 },
             ],
         },
     ]
 }
 
+/// Exercises whose prerequisites all appear in `completed` (exercise
+/// names), in curriculum order. Completed exercises are included.
+pub fn unlocked_exercises<'a>(
+    curriculum: &'a [CurriculumStage],
+    completed: &[String],
+) -> Vec<&'a CurriculumExercise> {
+    curriculum
+        .iter()
+        .flat_map(|stage| &stage.exercises)
+        .filter(|ex| ex.prerequisites.iter().all(|p| completed.contains(p)))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -263,4 +306,45 @@ mod tests {
             }
         }
     }
+    #[test]
+    fn test_prerequisites_come_earlier() {
+        let curriculum = get_curriculum();
+        let exercises: Vec<&CurriculumExercise> =
+            curriculum.iter().flat_map(|s| &s.exercises).collect();
+        for (i, ex) in exercises.iter().enumerate() {
+            for prereq in &ex.prerequisites {
+                assert!(
+                    exercises[..i].iter().any(|e| &e.name == prereq),
+                    "'{}' requires unknown or later exercise '{}'",
+                    ex.name,
+                    prereq
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_unlocked_exercises() {
+        let curriculum = get_curriculum();
+        let names = |completed: &[String]| -> Vec<String> {
+            unlocked_exercises(&curriculum, completed)
+                .iter()
+                .map(|ex| ex.name.clone())
+                .collect()
+        };
+
+        assert_eq!(names(&[]), vec!["Long Tones"]);
+
+        let stage_one: Vec<String> = curriculum[0].exercises.iter().map(|ex| ex.name.clone()).collect();
+        let unlocked = names(&stage_one);
+        assert!(unlocked.contains(&"Scales in C, F, G".to_string()));
+        assert!(unlocked.contains(&"Simple Lip Slurs".to_string()));
+        assert!(unlocked.contains(&"Chromatic Scale".to_string()));
+        assert!(!unlocked.contains(&"Interval Training".to_string()));
+
+        // Long tones alone unlock the slurs but not the scales
+        let unlocked = names(&["Long Tones".to_string()]);
+        assert!(unlocked.contains(&"Simple Lip Slurs".to_string()));
+        assert!(!unlocked.contains(&"Scales in C, F, G".to_string()));
+    }
 }
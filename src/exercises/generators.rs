@@ -1,6 +1,7 @@
-use crate::scoring::types::{MeasureInfo, NoteEvent, Score};
+use crate::scoring::types::{Clef, MeasureInfo, NoteEvent, Score, TransposeInfo};
+use crate::transposition::concert_to_written;
 
-use crate::parser::musicxml::midi_from_pitch;
+use crate::parser::musicxml::{beats_to_note_type, midi_from_pitch};
 
 pub fn generate(exercise_type: &str, key: &str, tempo: f64) -> Result<Score, String> {
     generate_with_options(exercise_type, key, tempo, None, None)
@@ -18,9 +19,17 @@ pub fn generate_with_options(
 
     let mut score = match exercise_type {
         "long_tones" => Ok(generate_long_tones(root_midi, tempo, diff)),
-        "major_scale" => Ok(generate_major_scale(root_midi, tempo, diff)),
-        "chromatic" => Ok(generate_chromatic(root_midi, tempo, diff)),
+        "long_tones_dynamics" => Ok(generate_long_tones_dynamics(root_midi, tempo)),
+        "major_scale" => Ok(generate_major_scale(
+            root_midi,
+            tempo,
+            diff,
+            1,
+            ScaleDirection::UpDown,
+        )),
+        "chromatic" => Ok(generate_chromatic(root_midi, tempo, diff, ScaleDirection::UpDown)),
         "lip_slurs" => Ok(generate_lip_slurs(root_midi, tempo)),
+        "flexibility" => Ok(generate_flexibility(root_midi, tempo)),
         "intervals" => Ok(generate_intervals(root_midi, tempo)),
         "arpeggios" => Ok(generate_arpeggios(root_midi, tempo)),
         "tonguing" => Ok(generate_tonguing(root_midi, tempo, diff)),
@@ -43,6 +52,73 @@ pub fn generate_with_options(
     Ok(score)
 }
 
+/// An exercise in concert `key` written out for a transposing instrument:
+/// notes are shifted to written pitch and `transpose` is set, so the
+/// sounding pitch is what was asked for. A concert C scale for Bb trumpet
+/// is written in D with two sharps.
+pub fn generate_for_instrument(
+    exercise_type: &str,
+    key: &str,
+    tempo: f64,
+    transpose: &TransposeInfo,
+) -> Result<Score, String> {
+    let mut score = generate(exercise_type, key, tempo)?;
+    for note in score.notes.iter_mut().filter(|n| !n.is_rest) {
+        note.midi = concert_to_written(note.midi, transpose);
+    }
+    // Inverse of Score::concert_key_fifths: move the key the other way
+    // around the circle of fifths, keeping it within 7 sharps or flats
+    let shift = (-transpose.chromatic * 7).rem_euclid(12);
+    let shift = if shift > 6 { shift - 12 } else { shift };
+    let fifths = score.key_fifths + shift;
+    score.key_fifths = if fifths > 7 {
+        fifths - 12
+    } else if fifths < -7 {
+        fifths + 12
+    } else {
+        fifths
+    };
+    score.transpose = Some(transpose.clone());
+    Ok(score)
+}
+
+/// Generate a warmup routine from a list of (exercise_type, key, tempo) specs.
+/// Each exercise starts on a fresh measure, separated by a one-measure rest.
+/// The routine plays at the first exercise's tempo; exercises at another
+/// tempo have their beats scaled so they still take as long as asked (one
+/// at 120 in a routine at 60 runs in half-length beats). Their notes keep
+/// the written `note_type`.
+pub fn generate_routine(specs: &[(&str, &str, f64)]) -> Result<Score, String> {
+    let first = specs.first().ok_or("Routine has no exercises")?;
+    if specs.iter().any(|&(_, _, tempo)| tempo <= 0.0) {
+        return Err("Routine tempos must be positive".to_string());
+    }
+    let mut notes = Vec::new();
+    let mut offset = 0.0;
+
+    for (i, &(exercise_type, key, tempo)) in specs.iter().enumerate() {
+        if i > 0 {
+            // Round up to the next barline, then rest for a full measure
+            offset = (offset / 4.0_f64).ceil() * 4.0;
+            let measure = (offset / 4.0) as u32 + 1;
+            notes.push(make_rest(offset, 4.0, measure));
+            offset += 4.0;
+        }
+
+        let exercise = generate(exercise_type, key, tempo)?;
+        let scale = first.2 / tempo;
+        for mut note in exercise.notes {
+            note.start_beat = offset + note.start_beat * scale;
+            note.duration_beats *= scale;
+            note.measure_number = (note.start_beat / 4.0) as u32 + 1;
+            notes.push(note);
+        }
+        offset += exercise.total_beats * scale;
+    }
+
+    Ok(build_score(notes, first.2))
+}
+
 fn key_to_midi(key: &str) -> Result<i32, String> {
     // Parse key like "C4", "F4", "Bb3", etc.
     let key = key.trim();
@@ -54,10 +130,10 @@ fn key_to_midi(key: &str) -> Result<i32, String> {
     let step = chars.next().unwrap();
     let rest: String = chars.collect();
 
-    let (alter, octave_str) = if rest.starts_with('#') {
-        (1, &rest[1..])
-    } else if rest.starts_with('b') {
-        (-1, &rest[1..])
+    let (alter, octave_str) = if let Some(stripped) = rest.strip_prefix('#') {
+        (1, stripped)
+    } else if let Some(stripped) = rest.strip_prefix('b') {
+        (-1, stripped)
     } else {
         (0, rest.as_str())
     };
@@ -88,6 +164,8 @@ fn build_score(notes: Vec<NoteEvent>, tempo: f64) -> Score {
             duration_beats: 4.0,
             time_sig_num: 4,
             time_sig_den: 4,
+            clef: Clef::treble(),
+            unmetered: false,
         })
         .collect();
 
@@ -99,25 +177,27 @@ fn build_score(notes: Vec<NoteEvent>, tempo: f64) -> Score {
         transpose: None,
         title: None,
         total_beats,
+        wedges: Vec::new(),
     }
 }
 
 fn make_note(start_beat: f64, duration_beats: f64, midi: i32, measure: u32) -> NoteEvent {
-    let note_type = match duration_beats as u32 {
-        4 => "whole",
-        2 => "half",
-        1 => "quarter",
-        _ => "quarter",
-    }
-    .to_string();
-
     NoteEvent {
         start_beat,
         duration_beats,
         midi,
         is_rest: false,
         measure_number: measure,
-        note_type,
+        note_type: beats_to_note_type(duration_beats).to_string(),
+        microtone_cents: 0.0,
+        articulations: Vec::new(),
+        beam_group: None,
+        dynamics: None,
+        velocity: None,
+        ornaments: Vec::new(),
+        accidental: None,
+        glissando_to: false,
+        accompaniment: false,
     }
 }
 
@@ -128,7 +208,16 @@ fn make_rest(start_beat: f64, duration_beats: f64, measure: u32) -> NoteEvent {
         midi: -1,
         is_rest: true,
         measure_number: measure,
-        note_type: "quarter".to_string(),
+        note_type: beats_to_note_type(duration_beats).to_string(),
+        microtone_cents: 0.0,
+        articulations: Vec::new(),
+        beam_group: None,
+        dynamics: None,
+        velocity: None,
+        ornaments: Vec::new(),
+        accidental: None,
+        glissando_to: false,
+        accompaniment: false,
     }
 }
 
@@ -164,55 +253,261 @@ fn generate_long_tones(root_midi: i32, tempo: f64, difficulty: u8) -> Score {
     build_score(notes, tempo)
 }
 
-fn generate_major_scale(root_midi: i32, tempo: f64, difficulty: u8) -> Score {
+/// Dynamic shape for a long-tone swell: pp, crescendo to ff, back to pp.
+pub const SWELL_DYNAMICS: &str = "pp<ff>pp";
+
+/// Long tones for breath control: each scale degree is a whole-note swell
+/// tagged with `SWELL_DYNAMICS`, followed by a measure's rest to breathe.
+pub fn generate_long_tones_dynamics(root_midi: i32, tempo: f64) -> Score {
     let intervals = [0, 2, 4, 5, 7, 9, 11, 12];
-    let dur = note_duration_for_difficulty(difficulty);
     let mut notes = Vec::new();
     let mut beat = 0.0;
 
-    // Up
     for &interval in &intervals {
         let measure = (beat / 4.0) as u32 + 1;
-        notes.push(make_note(beat, dur, root_midi + interval, measure));
-        beat += dur;
+        let mut note = make_note(beat, 4.0, root_midi + interval, measure);
+        note.dynamics = Some(SWELL_DYNAMICS.to_string());
+        notes.push(note);
+        beat += 4.0;
+        notes.push(make_rest(beat, 4.0, measure + 1));
+        beat += 4.0;
+    }
+
+    build_score(notes, tempo)
+}
+
+const MAJOR_SCALE_STEPS: [i32; 7] = [0, 2, 4, 5, 7, 9, 11];
+
+/// Parse a whitespace- or comma-separated interval pattern such as "0 4 7 12".
+pub fn parse_pattern(pattern: &str) -> Result<Vec<i32>, String> {
+    let intervals = pattern
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|t| !t.is_empty())
+        .map(|t| {
+            t.parse::<i32>()
+                .map_err(|_| format!("Invalid interval in pattern: {}", t))
+        })
+        .collect::<Result<Vec<i32>, String>>()?;
+    if intervals.is_empty() {
+        return Err("Empty interval pattern".to_string());
     }
-    // Down
-    for &interval in intervals[..7].iter().rev() {
+    Ok(intervals)
+}
+
+/// Play an interval pattern (semitones above a root) as quarter notes,
+/// `repeats` times with a rest after each. With `step_up`, each repetition
+/// starts on the next degree of the root's major scale.
+pub fn generate_pattern(
+    root_midi: i32,
+    tempo: f64,
+    intervals: &[i32],
+    repeats: u32,
+    step_up: bool,
+) -> Score {
+    let mut notes = Vec::new();
+    let mut beat = 0.0;
+
+    for rep in 0..repeats as usize {
+        let start = if step_up {
+            root_midi + MAJOR_SCALE_STEPS[rep % 7] + 12 * (rep / 7) as i32
+        } else {
+            root_midi
+        };
+        for &interval in intervals {
+            let measure = (beat / 4.0) as u32 + 1;
+            notes.push(make_note(beat, 1.0, start + interval, measure));
+            beat += 1.0;
+        }
         let measure = (beat / 4.0) as u32 + 1;
-        notes.push(make_note(beat, dur, root_midi + interval, measure));
-        beat += dur;
+        notes.push(make_rest(beat, 1.0, measure));
+        beat += 1.0;
     }
-    // End on root whole note
-    let measure = (beat / 4.0) as u32 + 1;
-    notes.push(make_note(beat, 4.0, root_midi, measure));
 
     build_score(notes, tempo)
 }
 
-fn generate_chromatic(root_midi: i32, tempo: f64, difficulty: u8) -> Score {
-    let dur = note_duration_for_difficulty(difficulty);
+/// Call-and-response ear training: a quarter-note phrase on `root_midi +
+/// interval`, a rest to the next barline (a full measure if the phrase ends
+/// on one), then the same phrase again for the student to echo.
+pub fn generate_echo(pattern_intervals: &[i32], root_midi: i32, tempo: f64) -> Score {
+    let phrase_beats = pattern_intervals.len() as f64;
+    let echo_start = (phrase_beats / 4.0).floor() * 4.0 + 4.0;
+    let mut notes = Vec::new();
+
+    for (i, offset) in [0.0, echo_start].into_iter().enumerate() {
+        if i > 0 {
+            let measure = (phrase_beats / 4.0) as u32 + 1;
+            notes.push(make_rest(phrase_beats, echo_start - phrase_beats, measure));
+        }
+        for (j, &interval) in pattern_intervals.iter().enumerate() {
+            let beat = offset + j as f64;
+            let measure = (beat / 4.0) as u32 + 1;
+            notes.push(make_note(beat, 1.0, root_midi + interval, measure));
+        }
+    }
+
+    build_score(notes, tempo)
+}
+
+/// Clarke-style flow study: `cell` (semitones above the root) as eighth
+/// notes, repeated `steps` times a semitone higher each time. Each
+/// repetition is followed by a rest of at least a beat that brings the
+/// next one back onto a beat.
+pub fn generate_flow_study(root_midi: i32, tempo: f64, cell: &[i32], steps: u32) -> Score {
     let mut notes = Vec::new();
     let mut beat = 0.0;
 
-    // Up one octave
-    for i in 0..=12 {
+    for step in 0..steps as i32 {
+        for &interval in cell {
+            let measure = (beat / 4.0) as u32 + 1;
+            notes.push(make_note(beat, 0.5, root_midi + step + interval, measure));
+            beat += 0.5;
+        }
+        let rest_beats = (beat + 1.0).ceil() - beat;
         let measure = (beat / 4.0) as u32 + 1;
-        notes.push(make_note(beat, dur, root_midi + i, measure));
-        beat += dur;
+        notes.push(make_rest(beat, rest_beats, measure));
+        beat += rest_beats;
     }
-    // Down
-    for i in (0..12).rev() {
+
+    build_score(notes, tempo)
+}
+
+/// Intonation practice against a drone: the exercise plus a `drone_midi`
+/// quarter note on every beat, stacked as a chord member on the exercise
+/// note starting there (if any) and listed after it. Drone notes are marked
+/// `accompaniment`, so analysis grades only the exercise.
+pub fn generate_with_drone(
+    exercise_type: &str,
+    key: &str,
+    tempo: f64,
+    drone_midi: i32,
+) -> Result<Score, String> {
+    let mut score = generate(exercise_type, key, tempo)?;
+    for beat in 0..score.total_beats.ceil() as u32 {
+        let beat = beat as f64;
+        let measure = score.measure_at(beat).map_or(1, |m| m.number);
+        let mut drone = make_note(beat, 1.0, drone_midi, measure);
+        drone.accompaniment = true;
+        score.notes.push(drone);
+    }
+    // Stable: the exercise note stays first at a shared onset
+    score.notes.sort_by(|a, b| {
+        a.start_beat
+            .partial_cmp(&b.start_beat)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    Ok(score)
+}
+
+/// Modal scale: the major scale of `parent_root_midi` played from degree
+/// `mode` (1 Ionian, 2 Dorian, 3 Phrygian, 4 Lydian, 5 Mixolydian,
+/// 6 Aeolian, 7 Locrian) up an octave and back, ending on that degree.
+pub fn generate_modal_scale(
+    parent_root_midi: i32,
+    tempo: f64,
+    difficulty: u8,
+    mode: u8,
+) -> Result<Score, String> {
+    if !(1..=7).contains(&mode) {
+        return Err(format!("Mode must be 1-7, got {}", mode));
+    }
+    Ok(generate_major_scale(parent_root_midi, tempo, difficulty, mode, ScaleDirection::UpDown))
+}
+
+/// Which way a scale runs over its octave.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ScaleDirection {
+    /// From the root up to the octave, held.
+    Up,
+    /// From the octave down to the root, held.
+    Down,
+    /// Up to the octave and back, ending on a held root.
+    #[default]
+    UpDown,
+}
+
+/// A scale exercise ("major_scale" or "chromatic") running only in
+/// `direction`, at the same note lengths as `generate_with_options`.
+pub fn generate_scale(
+    exercise_type: &str,
+    key: &str,
+    tempo: f64,
+    difficulty: Option<u8>,
+    direction: ScaleDirection,
+) -> Result<Score, String> {
+    let root_midi = key_to_midi(key)?;
+    let diff = difficulty.unwrap_or(2);
+    match exercise_type {
+        "major_scale" => Ok(generate_major_scale(root_midi, tempo, diff, 1, direction)),
+        "chromatic" => Ok(generate_chromatic(root_midi, tempo, diff, direction)),
+        _ => Err(format!("Not a scale exercise: {}", exercise_type)),
+    }
+}
+
+/// Play one octave of `steps` (semitones above `root_midi`, root through
+/// octave inclusive) in `direction` at `dur` beats, holding the last note
+/// for `final_dur`.
+fn scale_line(
+    root_midi: i32,
+    steps: &[i32],
+    direction: ScaleDirection,
+    dur: f64,
+    final_dur: f64,
+    tempo: f64,
+) -> Score {
+    let top = steps.len() - 1;
+    let (line, last): (Vec<i32>, i32) = match direction {
+        ScaleDirection::Up => (steps[..top].to_vec(), steps[top]),
+        ScaleDirection::Down => (steps[1..].iter().rev().copied().collect(), steps[0]),
+        ScaleDirection::UpDown => {
+            (steps.iter().chain(steps[..top].iter().rev()).copied().collect(), steps[0])
+        }
+    };
+    let mut notes = Vec::new();
+    let mut beat = 0.0;
+
+    for &step in &line {
         let measure = (beat / 4.0) as u32 + 1;
-        notes.push(make_note(beat, dur, root_midi + i, measure));
+        notes.push(make_note(beat, dur, root_midi + step, measure));
         beat += dur;
     }
-    // End on root
     let measure = (beat / 4.0) as u32 + 1;
-    notes.push(make_note(beat, 2.0, root_midi, measure));
+    notes.push(make_note(beat, final_dur, root_midi + last, measure));
 
     build_score(notes, tempo)
 }
 
+fn generate_major_scale(
+    root_midi: i32,
+    tempo: f64,
+    difficulty: u8,
+    mode: u8,
+    direction: ScaleDirection,
+) -> Score {
+    // Rotate the major scale to start on the mode's degree
+    let intervals: Vec<i32> = (0..8)
+        .map(|i| {
+            let degree = (mode as usize - 1) + i;
+            MAJOR_SCALE_STEPS[degree % 7] + 12 * (degree / 7) as i32
+        })
+        .collect();
+    let dur = note_duration_for_difficulty(difficulty);
+    // Ends on a whole note
+    scale_line(root_midi, &intervals, direction, dur, 4.0, tempo)
+}
+
+fn generate_chromatic(
+    root_midi: i32,
+    tempo: f64,
+    difficulty: u8,
+    direction: ScaleDirection,
+) -> Score {
+    let dur = note_duration_for_difficulty(difficulty);
+    let steps: Vec<i32> = (0..=12).collect();
+    // Ends on a half note
+    scale_line(root_midi, &steps, direction, dur, 2.0, tempo)
+}
+
 fn generate_lip_slurs(root_midi: i32, tempo: f64) -> Score {
     // Lip slurs move between harmonics on same fingering
     // Open: C4(60)-G4(67)-C5(72)
@@ -242,6 +537,34 @@ fn generate_lip_slurs(root_midi: i32, tempo: f64) -> Score {
     build_score(notes, tempo)
 }
 
+/// The seven valve combinations in descending order; each lowers the open
+/// overtone series by one more semitone.
+pub const FLEXIBILITY_FINGERINGS: [&str; 7] = ["0", "2", "1", "1-2", "2-3", "1-3", "1-2-3"];
+
+/// Flexibility routine walking the overtone series on each fingering in
+/// `FLEXIBILITY_FINGERINGS`: a bar slurring partials 2-3-4-3 (root, 5th,
+/// octave, 5th) as quarters, then the root as a half note and a half rest.
+/// `base_midi` is the open second partial; each fingering starts a semitone
+/// lower than the last.
+pub fn generate_flexibility(base_midi: i32, tempo: f64) -> Score {
+    let mut notes = Vec::new();
+    let mut beat = 0.0;
+
+    for fingering in 0..FLEXIBILITY_FINGERINGS.len() as i32 {
+        let root = base_midi - fingering;
+        let measure = (beat / 4.0) as u32 + 1;
+        for interval in [0, 7, 12, 7] {
+            notes.push(make_note(beat, 1.0, root + interval, measure));
+            beat += 1.0;
+        }
+        notes.push(make_note(beat, 2.0, root, measure + 1));
+        notes.push(make_rest(beat + 2.0, 2.0, measure + 1));
+        beat += 4.0;
+    }
+
+    build_score(notes, tempo)
+}
+
 fn generate_intervals(root_midi: i32, tempo: f64) -> Score {
     // Practice intervals: 3rds, 4ths, 5ths, octaves ascending and descending
     let interval_sizes = [3, 4, 5, 7, 12]; // minor 3rd, major 3rd, 4th, 5th, octave
@@ -401,13 +724,36 @@ mod tests {
         assert_eq!(score.notes[7].midi, 72);
     }
 
+    #[test]
+    fn test_scale_directions() {
+        let up = generate_scale("major_scale", "C4", 120.0, None, ScaleDirection::Up).unwrap();
+        let midis: Vec<i32> = up.notes.iter().map(|n| n.midi).collect();
+        assert_eq!(midis, vec![60, 62, 64, 65, 67, 69, 71, 72]);
+        assert_eq!(up.notes[7].note_type, "whole");
+
+        let down = generate_scale("major_scale", "C4", 120.0, None, ScaleDirection::Down).unwrap();
+        let midis: Vec<i32> = down.notes.iter().map(|n| n.midi).collect();
+        assert_eq!(midis, vec![72, 71, 69, 67, 65, 64, 62, 60]);
+
+        // Up and down is the default, as generated by `generate`
+        let both = generate_scale("chromatic", "C4", 120.0, None, ScaleDirection::default());
+        let plain = generate("chromatic", "C4", 120.0).unwrap();
+        assert_eq!(both.unwrap().notes.len(), plain.notes.len());
+        let up = generate_scale("chromatic", "C4", 120.0, None, ScaleDirection::Up).unwrap();
+        assert_eq!((up.notes.len(), up.notes[12].midi), (13, 72));
+
+        assert!(generate_scale("arpeggios", "C4", 120.0, None, ScaleDirection::Up).is_err());
+    }
+
     #[test]
     fn test_generate_all_types() {
         for exercise_type in &[
             "long_tones",
+            "long_tones_dynamics",
             "major_scale",
             "chromatic",
             "lip_slurs",
+            "flexibility",
             "intervals",
             "arpeggios",
             "tonguing",
@@ -438,6 +784,14 @@ mod tests {
         assert!(score_easy.total_beats > score_hard.total_beats);
     }
 
+    #[test]
+    fn test_eighth_note_type() {
+        let score = generate_with_options("major_scale", "C4", 100.0, Some(4), None).unwrap();
+        let first = &score.notes[0];
+        assert_eq!(first.duration_beats, 0.5);
+        assert_eq!(first.note_type, "eighth");
+    }
+
     #[test]
     fn test_generate_with_midi_range() {
         let score = generate_with_options("chromatic", "C4", 100.0, None, Some((60, 66))).unwrap();
@@ -453,6 +807,233 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_generate_routine() {
+        let long_tones = generate("long_tones", "C4", 80.0).unwrap();
+        let scale = generate("major_scale", "C4", 100.0).unwrap();
+        let routine =
+            generate_routine(&[("long_tones", "C4", 80.0), ("major_scale", "C4", 100.0)]).unwrap();
+
+        let sounding = routine.notes.iter().filter(|n| !n.is_rest).count();
+        assert_eq!(sounding, long_tones.notes.len() + scale.notes.len());
+        assert_eq!(routine.notes.iter().filter(|n| n.is_rest).count(), 1);
+        assert!(routine
+            .notes
+            .windows(2)
+            .all(|w| w[1].start_beat >= w[0].start_beat));
+        assert_eq!(routine.tempo, 80.0);
+        // The scale keeps its 100 bpm speed in the 80 bpm routine
+        assert_eq!(
+            routine.total_beats,
+            long_tones.total_beats + 4.0 + scale.total_beats * 0.8
+        );
+        let seconds = |score: &Score| score.total_beats * 60.0 / score.tempo;
+        let scale_start = long_tones.total_beats + 4.0;
+        assert!(
+            (seconds(&routine) - scale_start * 0.75 - seconds(&scale)).abs() < 1e-9,
+            "scale should last as long as it does on its own"
+        );
+        let first_scale_note = routine.notes.iter().find(|n| n.start_beat >= scale_start).unwrap();
+        assert_eq!(first_scale_note.duration_beats, scale.notes[0].duration_beats * 0.8);
+        assert_eq!(first_scale_note.measure_number, (scale_start / 4.0) as u32 + 1);
+        assert_eq!(routine.measures.len() as f64, (routine.total_beats / 4.0).ceil());
+    }
+
+    #[test]
+    fn test_generate_routine_errors() {
+        assert!(generate_routine(&[]).is_err());
+        assert!(generate_routine(&[("long_tones", "C4", 80.0), ("major_scale", "C4", 0.0)]).is_err());
+        assert!(generate_routine(&[("long_tones", "C4", 80.0), ("nonexistent", "C4", 80.0)]).is_err());
+    }
+
+    #[test]
+    fn test_modal_scale_mixolydian() {
+        let score = generate_modal_scale(60, 100.0, 3, 5).unwrap();
+        let ascending: Vec<i32> = score.notes[..8].iter().map(|n| n.midi).collect();
+        // G Mixolydian: G major with F natural instead of F#
+        assert_eq!(ascending, vec![67, 69, 71, 72, 74, 76, 77, 79]);
+        assert_eq!(ascending[6] - ascending[0], 10);
+        assert_eq!(score.notes.last().unwrap().midi, 67);
+
+        let ionian = generate_modal_scale(60, 100.0, 3, 1).unwrap();
+        let major = generate_with_options("major_scale", "C4", 100.0, Some(3), None).unwrap();
+        assert_eq!(
+            ionian.notes.iter().map(|n| n.midi).collect::<Vec<_>>(),
+            major.notes.iter().map(|n| n.midi).collect::<Vec<_>>()
+        );
+        assert!(generate_modal_scale(60, 100.0, 3, 8).is_err());
+    }
+
+    #[test]
+    fn test_generate_with_drone() {
+        let plain = generate("long_tones", "C4", 60.0).unwrap();
+        let score = generate_with_drone("long_tones", "C4", 60.0, 48).unwrap();
+        assert_eq!(score.total_beats, plain.total_beats);
+
+        // A drone note on every beat from start to finish
+        let drone: Vec<f64> = score
+            .notes
+            .iter()
+            .filter(|n| n.midi == 48)
+            .map(|n| n.start_beat)
+            .collect();
+        let beats: Vec<f64> = (0..plain.total_beats as u32).map(|b| b as f64).collect();
+        assert_eq!(drone, beats);
+
+        // The exercise itself is unchanged and leads each shared onset
+        let melody: Vec<(f64, i32)> = score
+            .notes
+            .iter()
+            .filter(|n| n.midi != 48)
+            .map(|n| (n.start_beat, n.midi))
+            .collect();
+        let expected: Vec<(f64, i32)> =
+            plain.notes.iter().map(|n| (n.start_beat, n.midi)).collect();
+        assert_eq!(melody, expected);
+        assert_ne!(score.notes[0].midi, 48);
+        assert!(generate_with_drone("bogus", "C4", 60.0, 48).is_err());
+    }
+
+    #[test]
+    fn test_drone_is_not_graded() {
+        use crate::scoring::analyzer::analyze_performance;
+        use crate::scoring::types::PlayedNote;
+
+        // A clean take of the scale alone scores the same with the drone on
+        let score = generate_with_drone("major_scale", "C4", 120.0, 48).unwrap();
+        let played: Vec<PlayedNote> = generate("major_scale", "C4", 120.0)
+            .unwrap()
+            .notes
+            .iter()
+            .filter(|n| !n.is_rest)
+            .map(|n| PlayedNote {
+                onset_beat: n.start_beat,
+                midi_float: n.midi as f64,
+                midi_rounded: n.midi,
+                confidence: 0.9,
+            })
+            .collect();
+        let result = analyze_performance(&score, &played, 50.0, 0.25);
+        assert_eq!(result.total_notes as usize, played.len());
+        assert_eq!(result.notes_missed, 0);
+        assert_eq!(result.overall_score, 100.0);
+        assert!(score.notes.iter().filter(|n| n.midi == 48).all(|n| n.accompaniment));
+        assert_eq!(score.midi_range(), generate("major_scale", "C4", 120.0).unwrap().midi_range());
+    }
+
+    #[test]
+    fn test_generate_echo() {
+        let score = generate_echo(&[0, 4, 7, 4, 0, 2], 60, 90.0);
+        let call: Vec<&NoteEvent> = score.notes.iter().take_while(|n| !n.is_rest).collect();
+        let rest = &score.notes[call.len()];
+        let echo: Vec<&NoteEvent> = score.notes[call.len() + 1..].iter().collect();
+
+        assert_eq!(call.len(), 6);
+        assert!(rest.is_rest);
+        assert_eq!((rest.start_beat, rest.duration_beats), (6.0, 2.0));
+        assert_eq!(echo.len(), call.len());
+        for (c, e) in call.iter().zip(&echo) {
+            assert_eq!(c.midi, e.midi);
+            assert_eq!(c.duration_beats, e.duration_beats);
+            assert_eq!(e.start_beat - c.start_beat, 8.0);
+        }
+        assert_eq!(score.total_beats, 14.0);
+
+        // A phrase filling a measure is answered after a full measure's rest
+        let score = generate_echo(&[0, 2, 4, 5], 60, 90.0);
+        assert_eq!((score.notes[4].start_beat, score.notes[4].duration_beats), (4.0, 4.0));
+        assert_eq!(score.notes[5].start_beat, 8.0);
+    }
+
+    #[test]
+    fn test_generate_for_bb_trumpet() {
+        let concert = generate("major_scale", "C4", 100.0).unwrap();
+        let bb = TransposeInfo::bb_trumpet();
+        let score = generate_for_instrument("major_scale", "C4", 100.0, &bb).unwrap();
+        // Concert C is written D for Bb trumpet
+        assert_eq!(score.notes[0].midi, 62);
+        assert_eq!(score.key_fifths, 2);
+        assert_eq!(score.transpose.as_ref().map(|t| t.chromatic), Some(-2));
+        assert_eq!(score.concert_key_fifths(), concert.key_fifths);
+
+        // Sounding pitches are unchanged once prepared for a concert mic
+        let sounding = crate::transposition::prepare_for_scoring(&score, true);
+        let midis = |s: &Score| s.notes.iter().map(|n| n.midi).collect::<Vec<_>>();
+        assert_eq!(midis(&sounding), midis(&concert));
+    }
+
+    #[test]
+    fn test_flow_study() {
+        let turn = [0, 2, 4, 2, 0];
+        let score = generate_flow_study(60, 120.0, &turn, 3);
+        let reps: Vec<&[NoteEvent]> = score.notes.split(|n| n.is_rest).collect();
+
+        // Three cells, each followed by a rest, then nothing
+        assert_eq!(reps.len(), 4);
+        assert!(reps[3].is_empty());
+        for (step, rep) in reps[..3].iter().enumerate() {
+            let midis: Vec<i32> = rep.iter().map(|n| n.midi).collect();
+            let expected: Vec<i32> = turn.iter().map(|i| 60 + step as i32 + i).collect();
+            assert_eq!(midis, expected);
+            assert!(rep.iter().all(|n| n.note_type == "eighth"));
+            // Each repetition starts on a beat
+            assert_eq!(rep[0].start_beat.fract(), 0.0);
+        }
+        let rests: Vec<f64> =
+            score.notes.iter().filter(|n| n.is_rest).map(|n| n.duration_beats).collect();
+        assert_eq!(rests, vec![1.5, 1.5, 1.5]);
+        assert_eq!(score.total_beats, 12.0);
+    }
+
+    #[test]
+    fn test_flexibility_fingering_series() {
+        let score = generate("flexibility", "C4", 80.0).unwrap();
+        let roots: Vec<i32> = score
+            .notes
+            .iter()
+            .filter(|n| n.note_type == "half" && !n.is_rest)
+            .map(|n| n.midi)
+            .collect();
+        // Open C4 down through 1-2-3 on F#3
+        assert_eq!(roots, vec![60, 59, 58, 57, 56, 55, 54]);
+        // Each fingering slurs up to its octave partial
+        assert_eq!(score.notes[2].midi, 72);
+        assert_eq!(score.notes[8].midi, 71);
+        assert_eq!(score.measures.len(), 14);
+    }
+
+    #[test]
+    fn test_long_tones_dynamics() {
+        let score = generate("long_tones_dynamics", "C4", 60.0).unwrap();
+        let sounding: Vec<_> = score.notes.iter().filter(|n| !n.is_rest).collect();
+        assert_eq!(sounding.len(), 8);
+        for note in &sounding {
+            assert_eq!(note.dynamics.as_deref(), Some(SWELL_DYNAMICS));
+            assert_eq!(note.note_type, "whole");
+        }
+        assert!(score.notes.iter().filter(|n| n.is_rest).all(|n| n.dynamics.is_none()));
+    }
+
+    #[test]
+    fn test_generate_pattern() {
+        let triad = parse_pattern("0 4 7 12").unwrap();
+        let score = generate_pattern(60, 100.0, &triad, 2, true);
+        let midis: Vec<i32> = score.notes.iter().filter(|n| !n.is_rest).map(|n| n.midi).collect();
+        assert_eq!(midis, vec![60, 64, 67, 72, 62, 66, 69, 74]);
+
+        let same = generate_pattern(60, 100.0, &triad, 2, false);
+        let midis: Vec<i32> = same.notes.iter().filter(|n| !n.is_rest).map(|n| n.midi).collect();
+        assert_eq!(midis, vec![60, 64, 67, 72, 60, 64, 67, 72]);
+        assert_eq!(same.notes.iter().filter(|n| n.is_rest).count(), 2);
+    }
+
+    #[test]
+    fn test_parse_pattern() {
+        assert_eq!(parse_pattern("0, 3,7").unwrap(), vec![0, 3, 7]);
+        assert!(parse_pattern("0 x 7").is_err());
+        assert!(parse_pattern("  ").is_err());
+    }
+
     #[test]
     fn test_tonguing_exercise() {
         let score = generate("tonguing", "C4", 120.0).unwrap();
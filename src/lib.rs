@@ -1,19 +1,40 @@
+//! Bb trumpet practice engine. With the default `std` feature this is the
+//! WASM facade over the parser, generators and analyzer. Without it, only the
+//! `midi`, `partials`, `pitch`, `scoring` and `transposition` modules are
+//! built, on `core` + `alloc`, for embedded targets.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
 use wasm_bindgen::prelude::*;
 
-mod exercises;
-mod parser;
-mod pitch;
+#[cfg(feature = "std")]
+pub mod exercises;
+#[cfg(not(feature = "std"))]
+mod math;
+pub mod midi;
+#[cfg(feature = "std")]
+pub mod parser;
+pub mod partials;
+pub mod pitch;
+#[cfg(feature = "std")]
+pub mod prelude;
 pub mod scoring;
 pub mod transposition;
 
+#[cfg(feature = "std")]
 use scoring::types::{PitchTrailPoint, PlayedNote, Score};
 
+#[cfg(feature = "std")]
 use std::cell::RefCell;
 
+#[cfg(feature = "std")]
 thread_local! {
-    static DETECTOR: RefCell<Option<pitch::yin::PitchDetector>> = RefCell::new(None);
+    static DETECTOR: RefCell<Option<pitch::yin::PitchDetector>> = const { RefCell::new(None) };
 }
 
+#[cfg(feature = "std")]
 #[wasm_bindgen]
 pub fn parse_musicxml(xml: &str) -> Result<JsValue, JsValue> {
     let score =
@@ -23,6 +44,7 @@ pub fn parse_musicxml(xml: &str) -> Result<JsValue, JsValue> {
 
 /// YIN-based pitch detection returning Float64Array [hz, confidence, midi_float].
 /// Uses a thread-local pre-allocated PitchDetector to avoid per-call allocations.
+#[cfg(feature = "std")]
 #[wasm_bindgen]
 pub fn detect_pitch(samples: &[f32], sample_rate: f32) -> js_sys::Float64Array {
     let result = DETECTOR.with(|cell| {
@@ -41,6 +63,7 @@ pub fn detect_pitch(samples: &[f32], sample_rate: f32) -> js_sys::Float64Array {
 }
 
 /// Analyze a performance: compare played notes against score.
+#[cfg(feature = "std")]
 #[wasm_bindgen]
 pub fn analyze_performance(
     score_js: JsValue,
@@ -83,6 +106,7 @@ pub fn analyze_performance(
 }
 
 /// Generate a warmup exercise, returning a Score.
+#[cfg(feature = "std")]
 #[wasm_bindgen]
 pub fn generate_exercise(
     exercise_type: &str,
@@ -103,6 +127,7 @@ pub fn generate_exercise(
 }
 
 /// Get the 4-stage curriculum structure.
+#[cfg(feature = "std")]
 #[wasm_bindgen]
 pub fn get_curriculum() -> Result<JsValue, JsValue> {
     let curriculum = exercises::curriculum::get_curriculum();
@@ -0,0 +1,68 @@
+//! Float functions for no_std builds. `core` has no inherent `sqrt`, `round`,
+//! `log2` etc., so without the `std` feature these forward to libm under the
+//! same method names and the calling code stays unchanged.
+
+pub(crate) trait FloatMath {
+    fn sqrt(self) -> Self;
+    fn powi(self, n: i32) -> Self;
+    fn powf(self, n: Self) -> Self;
+    fn round(self) -> Self;
+    fn floor(self) -> Self;
+    fn ceil(self) -> Self;
+    fn log2(self) -> Self;
+    fn cos(self) -> Self;
+}
+
+impl FloatMath for f64 {
+    fn sqrt(self) -> f64 {
+        libm::sqrt(self)
+    }
+    fn powi(self, n: i32) -> f64 {
+        libm::pow(self, n as f64)
+    }
+    fn powf(self, n: f64) -> f64 {
+        libm::pow(self, n)
+    }
+    fn round(self) -> f64 {
+        libm::round(self)
+    }
+    fn floor(self) -> f64 {
+        libm::floor(self)
+    }
+    fn ceil(self) -> f64 {
+        libm::ceil(self)
+    }
+    fn log2(self) -> f64 {
+        libm::log2(self)
+    }
+    fn cos(self) -> f64 {
+        libm::cos(self)
+    }
+}
+
+impl FloatMath for f32 {
+    fn sqrt(self) -> f32 {
+        libm::sqrtf(self)
+    }
+    fn powi(self, n: i32) -> f32 {
+        libm::powf(self, n as f32)
+    }
+    fn powf(self, n: f32) -> f32 {
+        libm::powf(self, n)
+    }
+    fn round(self) -> f32 {
+        libm::roundf(self)
+    }
+    fn floor(self) -> f32 {
+        libm::floorf(self)
+    }
+    fn ceil(self) -> f32 {
+        libm::ceilf(self)
+    }
+    fn log2(self) -> f32 {
+        libm::log2f(self)
+    }
+    fn cos(self) -> f32 {
+        libm::cosf(self)
+    }
+}
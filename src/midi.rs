@@ -0,0 +1,143 @@
+use alloc::vec::Vec;
+
+#[cfg(not(feature = "std"))]
+use crate::math::FloatMath;
+use crate::scoring::types::Score;
+
+/// Ticks per quarter note in exported files.
+pub const TICKS_PER_BEAT: u16 = 480;
+
+/// Velocity for notes without a `<sound dynamics>` value.
+pub const DEFAULT_VELOCITY: u8 = 80;
+
+/// Append `value` as a MIDI variable-length quantity.
+fn push_vlq(out: &mut Vec<u8>, value: u32) {
+    let mut groups = [0u8; 5];
+    let mut len = 0;
+    let mut v = value;
+    loop {
+        groups[len] = (v & 0x7f) as u8;
+        len += 1;
+        v >>= 7;
+        if v == 0 {
+            break;
+        }
+    }
+    for i in (0..len).rev() {
+        out.push(if i > 0 { groups[i] | 0x80 } else { groups[i] });
+    }
+}
+
+/// Export a score as a single-track Standard MIDI File (format 0) at concert
+/// pitch, for playback. Each note uses its `velocity`, falling back to
+/// `DEFAULT_VELOCITY`; rests and notes outside the MIDI range are skipped.
+pub fn score_to_midi(score: &Score) -> Vec<u8> {
+    let chromatic = score.transpose.as_ref().map_or(0, |t| t.chromatic);
+    let to_ticks = |beats: f64| (beats.max(0.0) * TICKS_PER_BEAT as f64).round() as u32;
+
+    // (tick, is_note_on, status-data bytes); offs sort before ons at a tick
+    let mut events: Vec<(u32, bool, [u8; 3])> = Vec::new();
+    for note in score.notes.iter().filter(|n| !n.is_rest) {
+        let pitch = note.midi + chromatic;
+        if !(0..=127).contains(&pitch) {
+            continue;
+        }
+        let velocity = note.velocity.unwrap_or(DEFAULT_VELOCITY).clamp(1, 127);
+        let start = to_ticks(note.start_beat);
+        let end = to_ticks(note.start_beat + note.duration_beats).max(start);
+        events.push((start, true, [0x90, pitch as u8, velocity]));
+        events.push((end, false, [0x80, pitch as u8, 0]));
+    }
+    events.sort_by_key(|&(tick, is_on, _)| (tick, is_on));
+
+    let mut track = Vec::new();
+    let micros_per_beat = if score.tempo > 0.0 {
+        (60_000_000.0 / score.tempo).round() as u32
+    } else {
+        500_000
+    };
+    push_vlq(&mut track, 0);
+    track.extend_from_slice(&[0xff, 0x51, 0x03]);
+    track.extend_from_slice(&micros_per_beat.to_be_bytes()[1..]);
+
+    let mut last_tick = 0;
+    for (tick, _, bytes) in events {
+        push_vlq(&mut track, tick - last_tick);
+        track.extend_from_slice(&bytes);
+        last_tick = tick;
+    }
+    push_vlq(&mut track, 0);
+    track.extend_from_slice(&[0xff, 0x2f, 0x00]);
+
+    let mut out = Vec::with_capacity(track.len() + 22);
+    out.extend_from_slice(b"MThd");
+    out.extend_from_slice(&6u32.to_be_bytes());
+    out.extend_from_slice(&0u16.to_be_bytes());
+    out.extend_from_slice(&1u16.to_be_bytes());
+    out.extend_from_slice(&TICKS_PER_BEAT.to_be_bytes());
+    out.extend_from_slice(b"MTrk");
+    out.extend_from_slice(&(track.len() as u32).to_be_bytes());
+    out.extend_from_slice(&track);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::musicxml::parse_musicxml;
+
+    /// Velocities of the note-on events in an exported file, in order.
+    fn note_on_velocities(bytes: &[u8]) -> Vec<u8> {
+        // Skip the header chunk, track header and tempo event
+        let mut i = 14 + 8 + 7;
+        let mut velocities = Vec::new();
+        while i < bytes.len() {
+            while bytes[i] & 0x80 != 0 {
+                i += 1;
+            }
+            i += 1;
+            match bytes[i] {
+                0x90 => velocities.push(bytes[i + 2]),
+                0xff => break,
+                _ => {}
+            }
+            i += 3;
+        }
+        velocities
+    }
+
+    #[test]
+    fn test_vlq() {
+        let encode = |v| {
+            let mut out = Vec::new();
+            push_vlq(&mut out, v);
+            out
+        };
+        assert_eq!(encode(0), vec![0x00]);
+        assert_eq!(encode(0x7f), vec![0x7f]);
+        assert_eq!(encode(480), vec![0x83, 0x60]);
+        assert_eq!(encode(0x0fff_ffff), vec![0xff, 0xff, 0xff, 0x7f]);
+    }
+
+    #[test]
+    fn test_sound_dynamics_sets_velocity() {
+        let xml = r#"<score-partwise version="3.1"><part id="P1"><measure number="1">
+            <attributes><divisions>1</divisions></attributes>
+            <note><pitch><step>C</step><octave>5</octave></pitch><duration>1</duration></note>
+            <direction><sound dynamics="90"/></direction>
+            <note><pitch><step>D</step><octave>5</octave></pitch><duration>1</duration></note>
+            <note><pitch><step>E</step><octave>5</octave></pitch><duration>1</duration></note>
+            <direction><sound tempo="100" dynamics="40"/></direction>
+            <note><pitch><step>F</step><octave>5</octave></pitch><duration>1</duration></note>
+        </measure></part></score-partwise>"#;
+
+        let score = parse_musicxml(xml).unwrap();
+        let velocities: Vec<Option<u8>> = score.notes.iter().map(|n| n.velocity).collect();
+        assert_eq!(velocities, vec![None, Some(81), Some(81), Some(36)]);
+        assert_eq!(score.tempo, 100.0);
+
+        let bytes = score_to_midi(&score);
+        assert_eq!(&bytes[..4], b"MThd");
+        assert_eq!(note_on_velocities(&bytes), vec![DEFAULT_VELOCITY, 81, 81, 36]);
+    }
+}
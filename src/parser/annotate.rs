@@ -0,0 +1,204 @@
+use quick_xml::events::{BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::{Reader, Writer};
+
+use crate::parser::musicxml::parse_musicxml;
+use crate::scoring::types::{NoteEvent, NoteResult, PerformanceAnalysis, Score};
+use crate::transposition::written_to_concert;
+
+/// Note colors for each result status, for renderers that honor `color`.
+const CORRECT_COLOR: &str = "#2E7D32";
+const WRONG_PITCH_COLOR: &str = "#C62828";
+const MISSED_COLOR: &str = "#9E9E9E";
+
+/// Short label for a note's result, e.g. "correct +3c" or "wrong -42c".
+fn result_label(result: &NoteResult) -> String {
+    let cents = result
+        .pitch_error_cents
+        .map(|c| format!(" {:+.0}c", c))
+        .unwrap_or_default();
+    match result.status.as_str() {
+        "correct" => format!("correct{}", cents),
+        "wrong_pitch" => format!("wrong{}", cents),
+        other => other.to_string(),
+    }
+}
+
+/// Whether `result` grades `note`: the same onset, at written or (for an
+/// analysis of the score prepared at concert pitch) sounding pitch.
+fn grades(result: &NoteResult, note: &NoteEvent, score: &Score) -> bool {
+    let concert = score.transpose.as_ref().map(|t| written_to_concert(note.midi, t));
+    (result.target_beat - note.start_beat).abs() < 1e-6
+        && (result.target_midi == note.midi || Some(result.target_midi) == concert)
+}
+
+/// Re-emit `original_xml` with each analyzed note marked up for a notation
+/// renderer: a `color` attribute by status and a `<lyric name="analysis">`
+/// with the result and cents error. Each of `analysis.note_results` marks
+/// the note at its `target_beat` and `target_midi`, so an analysis of part
+/// of the piece (e.g. `analyze_measure`) lands on those notes; it must keep
+/// the score's own beats. Everything else is copied through unchanged.
+pub fn annotate_musicxml(
+    original_xml: &str,
+    analysis: &PerformanceAnalysis,
+) -> Result<String, String> {
+    let score = parse_musicxml(original_xml)?;
+    let mut used = vec![false; analysis.note_results.len()];
+    let results: Vec<Option<&NoteResult>> = score
+        .notes
+        .iter()
+        .map(|note| {
+            if note.is_rest {
+                return None;
+            }
+            let i = (0..used.len())
+                .find(|&i| !used[i] && grades(&analysis.note_results[i], note, &score))?;
+            used[i] = true;
+            Some(&analysis.note_results[i])
+        })
+        .collect();
+
+    let mut reader = Reader::from_str(original_xml);
+    let mut writer = Writer::new(Vec::new());
+    let mut note_index = 0;
+    let mut pending: Option<&NoteResult> = None;
+    let mut depth_in_note = 0u32;
+    let write = |writer: &mut Writer<Vec<u8>>, event: Event| {
+        writer.write_event(event).map_err(|e| format!("XML write error: {}", e))
+    };
+
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| format!("XML parse error: {}", e))?;
+        match event {
+            Event::Start(e) if e.name().as_ref() == b"note" && depth_in_note == 0 => {
+                depth_in_note = 1;
+                pending = results.get(note_index).copied().flatten();
+                note_index += 1;
+                let Some(result) = pending else {
+                    write(&mut writer, Event::Start(e))?;
+                    continue;
+                };
+                let color = match result.status.as_str() {
+                    "correct" => CORRECT_COLOR,
+                    "wrong_pitch" => WRONG_PITCH_COLOR,
+                    _ => MISSED_COLOR,
+                };
+                let mut start = BytesStart::new("note");
+                start.extend_attributes(
+                    e.attributes()
+                        .flatten()
+                        .filter(|a| a.key.as_ref() != b"color"),
+                );
+                start.push_attribute(("color", color));
+                write(&mut writer, Event::Start(start))?;
+            }
+            Event::Start(e) => {
+                if depth_in_note > 0 {
+                    depth_in_note += 1;
+                }
+                write(&mut writer, Event::Start(e))?;
+            }
+            Event::End(e) if depth_in_note == 1 && e.name().as_ref() == b"note" => {
+                depth_in_note = 0;
+                if let Some(result) = pending.take() {
+                    let mut lyric = BytesStart::new("lyric");
+                    lyric.push_attribute(("name", "analysis"));
+                    write(&mut writer, Event::Start(lyric))?;
+                    write(&mut writer, Event::Start(BytesStart::new("text")))?;
+                    write(&mut writer, Event::Text(BytesText::new(&result_label(result))))?;
+                    write(&mut writer, Event::End(BytesEnd::new("text")))?;
+                    write(&mut writer, Event::End(BytesEnd::new("lyric")))?;
+                }
+                write(&mut writer, Event::End(e))?;
+            }
+            Event::End(e) => {
+                depth_in_note = depth_in_note.saturating_sub(1);
+                write(&mut writer, Event::End(e))?;
+            }
+            Event::Eof => break,
+            other => write(&mut writer, other)?,
+        }
+    }
+
+    String::from_utf8(writer.into_inner()).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scoring::analyzer::{analyze_measure, analyze_performance};
+    use crate::scoring::types::PlayedNote;
+
+    #[test]
+    fn test_annotate_wrong_pitch() {
+        let xml = r##"<?xml version="1.0" encoding="UTF-8"?>
+<score-partwise version="3.1">
+  <part id="P1">
+    <measure number="1">
+      <attributes><divisions>1</divisions></attributes>
+      <note><pitch><step>C</step><octave>4</octave></pitch><duration>1</duration><type>quarter</type></note>
+      <note><rest/><duration>1</duration></note>
+      <note><pitch><step>E</step><octave>4</octave></pitch><duration>1</duration><type>quarter</type></note>
+      <note color="#000000"><pitch><step>G</step><octave>4</octave></pitch><duration>1</duration><type>quarter</type></note>
+    </measure>
+  </part>
+</score-partwise>"##;
+        let score = parse_musicxml(xml).unwrap();
+        // C4 in tune, E4 played as F4, G4 never played
+        let played = vec![
+            PlayedNote { onset_beat: 0.0, midi_float: 60.0, midi_rounded: 60, confidence: 0.9 },
+            PlayedNote { onset_beat: 2.0, midi_float: 65.0, midi_rounded: 65, confidence: 0.9 },
+        ];
+        let analysis = analyze_performance(&score, &played, 50.0, 0.3);
+
+        let annotated = annotate_musicxml(xml, &analysis).unwrap();
+        assert!(annotated.contains(
+            r##"<note color="#C62828"><pitch><step>E</step><octave>4</octave></pitch><duration>1</duration><type>quarter</type><lyric name="analysis"><text>wrong +100c</text></lyric></note>"##
+        ));
+        assert!(annotated.contains(r#"<text>correct +0c</text>"#));
+        // An existing color is replaced rather than duplicated
+        assert!(annotated.contains(r##"<note color="#9E9E9E"><pitch><step>G</step>"##));
+        assert!(annotated.contains(r#"<text>missed</text>"#));
+        // Rests are left alone and the annotated file still parses the same
+        assert!(annotated.contains("<note><rest/><duration>1</duration></note>"));
+        assert_eq!(parse_musicxml(&annotated).unwrap().notes.len(), score.notes.len());
+        assert!(annotated.starts_with(r#"<?xml version="1.0" encoding="UTF-8"?>"#));
+    }
+
+    #[test]
+    fn test_annotate_measure_analysis() {
+        let xml = r#"<score-partwise version="3.1">
+  <part id="P1">
+    <measure number="1">
+      <attributes><divisions>1</divisions><time><beats>2</beats><beat-type>4</beat-type></time></attributes>
+      <note><pitch><step>C</step><octave>4</octave></pitch><duration>1</duration></note>
+      <note><pitch><step>D</step><octave>4</octave></pitch><duration>1</duration></note>
+    </measure>
+    <measure number="2">
+      <note><pitch><step>E</step><octave>4</octave></pitch><duration>1</duration></note>
+      <note><pitch><step>F</step><octave>4</octave></pitch><duration>1</duration></note>
+    </measure>
+    <measure number="3">
+      <note><pitch><step>G</step><octave>4</octave></pitch><duration>2</duration></note>
+    </measure>
+  </part>
+</score-partwise>"#;
+        let score = parse_musicxml(xml).unwrap();
+        let played = vec![
+            PlayedNote { onset_beat: 2.0, midi_float: 64.0, midi_rounded: 64, confidence: 0.9 },
+            PlayedNote { onset_beat: 3.0, midi_float: 66.0, midi_rounded: 66, confidence: 0.9 },
+        ];
+        let analysis = analyze_measure(&score, &played, 2, 50.0, 0.3);
+
+        // Only measure 2's notes are marked, not the last notes of the piece
+        let annotated = annotate_musicxml(xml, &analysis).unwrap();
+        assert!(annotated.contains(
+            r##"<note color="#2E7D32"><pitch><step>E</step><octave>4</octave></pitch><duration>1</duration><lyric name="analysis"><text>correct +0c</text></lyric></note>"##
+        ));
+        assert!(annotated.contains(r##"<note color="#C62828"><pitch><step>F</step>"##));
+        for untouched in ["C", "D", "G"] {
+            assert!(annotated.contains(&format!("<note><pitch><step>{}</step>", untouched)));
+        }
+    }
+}
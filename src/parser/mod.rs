@@ -1 +1,2 @@
+pub mod annotate;
 pub mod musicxml;
@@ -1,7 +1,135 @@
-use quick_xml::events::Event;
+use std::ops::{Add, Div, Sub};
+
+use quick_xml::events::{BytesStart, Event};
 use quick_xml::Reader;
 
-use crate::scoring::types::{MeasureInfo, NoteEvent, Score, TransposeInfo};
+use crate::scoring::types::{Clef, MeasureInfo, NoteEvent, Score, TransposeInfo, WedgeSpan};
+use crate::transposition::transpose_for_instrument;
+
+/// Exact beat position or length as a reduced fraction. Durations are
+/// accumulated in this form so tuplet-heavy scores don't drift, and are
+/// converted to f64 only when building the Score. Arithmetic whose exact
+/// result would overflow (fine decimal divisions that change from measure
+/// to measure) falls back to `APPROX_DEN` steps rather than wrapping.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Beats {
+    num: i64,
+    den: i64,
+}
+
+/// Denominator of the approximation used when exact arithmetic overflows:
+/// divisible by every tuplet up to 16 and fine enough (about 1e-9 beats)
+/// not to matter.
+const APPROX_DEN: i64 = 720_720_000;
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+impl Beats {
+    const ZERO: Beats = Beats { num: 0, den: 1 };
+
+    fn new(num: i64, den: i64) -> Self {
+        let g = gcd(num, den).max(1) * den.signum();
+        Beats {
+            num: num / g,
+            den: den / g,
+        }
+    }
+
+    /// Parse a MusicXML decimal such as "3" or "1.5" without rounding, or
+    /// `None` if it is malformed or too large to hold exactly.
+    fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let (whole, frac) = text.split_once('.').unwrap_or((text, ""));
+        if frac.len() > 9 || !frac.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let den = 10i64.pow(frac.len() as u32);
+        let whole: i64 = if whole.is_empty() || whole == "-" {
+            0
+        } else {
+            whole.parse().ok()?
+        };
+        let frac: i64 = if frac.is_empty() { 0 } else { frac.parse().ok()? };
+        let frac = if text.starts_with('-') { -frac } else { frac };
+        Some(Beats::new(whole.checked_mul(den)?.checked_add(frac)?, den))
+    }
+
+    /// Nearest multiple of `1 / APPROX_DEN` to `value`.
+    fn approx(value: f64) -> Self {
+        Beats::new((value * APPROX_DEN as f64).round() as i64, APPROX_DEN)
+    }
+
+    /// `self + other` exactly, over the lcm of the denominators, or `None`
+    /// if that overflows.
+    fn checked_add(self, other: Beats) -> Option<Beats> {
+        let g = gcd(self.den, other.den);
+        let den = (self.den / g).checked_mul(other.den)?;
+        let num = self
+            .num
+            .checked_mul(other.den / g)?
+            .checked_add(other.num.checked_mul(self.den / g)?)?;
+        Some(Beats::new(num, den))
+    }
+
+    /// `self / other` exactly, cross-reducing first, or `None` if that
+    /// overflows.
+    fn checked_div(self, other: Beats) -> Option<Beats> {
+        let gn = gcd(self.num, other.num).max(1);
+        let gd = gcd(self.den, other.den).max(1);
+        let num = (self.num / gn).checked_mul(other.den / gd)?;
+        let den = (self.den / gd).checked_mul(other.num / gn)?;
+        Some(Beats::new(num, den))
+    }
+
+    fn is_zero(self) -> bool {
+        self.num == 0
+    }
+
+    fn to_f64(self) -> f64 {
+        self.num as f64 / self.den as f64
+    }
+}
+
+impl Add for Beats {
+    type Output = Beats;
+    fn add(self, other: Beats) -> Beats {
+        self.checked_add(other)
+            .unwrap_or_else(|| Beats::approx(self.to_f64() + other.to_f64()))
+    }
+}
+
+impl Sub for Beats {
+    type Output = Beats;
+    fn sub(self, other: Beats) -> Beats {
+        self + Beats {
+            num: -other.num,
+            den: other.den,
+        }
+    }
+}
+
+impl Div for Beats {
+    type Output = Beats;
+    fn div(self, other: Beats) -> Beats {
+        self.checked_div(other)
+            .unwrap_or_else(|| Beats::approx(self.to_f64() / other.to_f64()))
+    }
+}
+
+impl PartialOrd for Beats {
+    fn partial_cmp(&self, other: &Beats) -> Option<std::cmp::Ordering> {
+        // Denominators are positive; widened products cannot overflow
+        let lhs = self.num as i128 * other.den as i128;
+        let rhs = other.num as i128 * self.den as i128;
+        Some(lhs.cmp(&rhs))
+    }
+}
 
 pub fn midi_from_pitch(step: char, alter: i32, octave: i32) -> i32 {
     let base = match step {
@@ -17,20 +145,236 @@ pub fn midi_from_pitch(step: char, alter: i32, octave: i32) -> i32 {
     (octave + 1) * 12 + base + alter
 }
 
+/// MusicXML note type for a duration in beats (quarter = 1 beat): the
+/// longest type not exceeding it, so dotted durations report their base
+/// type. Non-positive durations report "quarter".
+pub fn beats_to_note_type(beats: f64) -> &'static str {
+    const TYPES: [(f64, &str); 8] = [
+        (8.0, "breve"),
+        (4.0, "whole"),
+        (2.0, "half"),
+        (1.0, "quarter"),
+        (0.5, "eighth"),
+        (0.25, "16th"),
+        (0.125, "32nd"),
+        (0.0625, "64th"),
+    ];
+    if beats <= 0.0 {
+        return "quarter";
+    }
+    TYPES
+        .iter()
+        .find(|&&(length, _)| beats >= length - 1e-9)
+        .map_or("64th", |&(_, name)| name)
+}
+
+/// Length in beats implied by a MusicXML note type and its number of dots,
+/// or `None` for an unknown type. Tuplet ratios are not applied.
+fn note_type_beats(note_type: &str, dots: u32) -> Option<Beats> {
+    let base = match note_type {
+        "breve" => Beats::new(8, 1),
+        "whole" => Beats::new(4, 1),
+        "half" => Beats::new(2, 1),
+        "quarter" => Beats::new(1, 1),
+        "eighth" => Beats::new(1, 2),
+        "16th" => Beats::new(1, 4),
+        "32nd" => Beats::new(1, 8),
+        "64th" => Beats::new(1, 16),
+        _ => return None,
+    };
+    // Each dot adds half the previous value: base * (2 - 1/2^dots)
+    let scale = 1i64 << dots.min(8);
+    Some(Beats::new(base.num * (2 * scale - 1), base.den * scale))
+}
+
+/// Children of `<ornaments>` kept on `NoteEvent::ornaments`. Others
+/// (`wavy-line` continuations, `accidental-mark`) only modify these.
+const ORNAMENT_MARKS: [&str; 12] = [
+    "trill-mark",
+    "turn",
+    "delayed-turn",
+    "inverted-turn",
+    "delayed-inverted-turn",
+    "vertical-turn",
+    "shake",
+    "mordent",
+    "inverted-mordent",
+    "schleifer",
+    "tremolo",
+    "haydn",
+];
+
+/// Text attribute of an element, e.g. `type` on `<wedge>`.
+fn text_attr(e: &BytesStart, key: &[u8]) -> Option<String> {
+    let attr = e.attributes().flatten().find(|a| a.key.as_ref() == key)?;
+    std::str::from_utf8(&attr.value).ok().map(|v| v.trim().to_string())
+}
+
+/// Numeric attribute of an element, e.g. `tempo` on `<sound>`.
+fn numeric_attr(e: &BytesStart, key: &[u8]) -> Option<f64> {
+    text_attr(e, key)?.parse().ok()
+}
+
+/// Apply a tempo mark, ignoring (with a warning) values that are not a
+/// positive, finite BPM so beat-to-seconds conversions stay sane. The
+/// previous tempo, initially the 120 default, stays in effect.
+fn set_tempo(value: f64, tempo: &mut f64, measure: u32, warnings: &mut Vec<String>) {
+    if value.is_finite() && value > 0.0 {
+        *tempo = value;
+    } else {
+        warnings.push(format!(
+            "Measure {}: ignoring invalid tempo {}; keeping {}",
+            measure, value, tempo
+        ));
+    }
+}
+
+/// Handle a `<wedge>` at `beat`: crescendo and diminuendo open a hairpin
+/// under the wedge's `number` (default 1), and `stop` closes the open one
+/// with that number into a span. `continue` marks need no action.
+fn wedge_event(
+    e: &BytesStart,
+    beat: Beats,
+    open: &mut Vec<(String, String, Beats)>,
+    wedges: &mut Vec<WedgeSpan>,
+) {
+    let number = text_attr(e, b"number").unwrap_or_else(|| "1".to_string());
+    match text_attr(e, b"type").as_deref() {
+        Some(direction @ ("crescendo" | "diminuendo")) => {
+            open.retain(|(n, _, _)| *n != number);
+            open.push((number, direction.to_string(), beat));
+        }
+        Some("stop") => {
+            if let Some(i) = open.iter().position(|(n, _, _)| *n == number) {
+                let (_, direction, start) = open.remove(i);
+                wedges.push(WedgeSpan {
+                    start_beat: start.to_f64(),
+                    end_beat: beat.to_f64(),
+                    direction,
+                });
+            }
+        }
+        _ => {}
+    }
+}
+
+/// MIDI velocity for a `<sound dynamics>` value, which is a percentage of
+/// the default forte velocity (90).
+fn sound_velocity(dynamics: f64) -> u8 {
+    (dynamics * 0.9).round().clamp(1.0, 127.0) as u8
+}
+
+/// Accent-type dynamics (sforzando, forte-piano) apply to the next note
+/// only; the prevailing level resumes after it.
+const MOMENTARY_DYNAMICS: [&str; 10] = ["sf", "sfz", "sffz", "sfp", "sfpp", "sfzp", "fz", "fp", "rf", "rfz"];
+
+/// Record a dynamics marking, kept as its raw name: momentary markings wait
+/// for the next note, others become the prevailing level.
+fn set_dynamics(marking: String, current: &mut Option<String>, accent: &mut Option<String>) {
+    if MOMENTARY_DYNAMICS.contains(&marking.as_str()) {
+        *accent = Some(marking);
+    } else {
+        *current = Some(marking);
+    }
+}
+
+/// Duration of a measure running from `start` to `end`, warning when it is
+/// longer than its time signature allows (a note spilling past the barline
+/// in malformed input). The real span is kept so measures stay contiguous
+/// and every beat belongs to one. Senza-misura measures have no length to
+/// enforce.
+fn measure_duration(
+    number: u32,
+    start: Beats,
+    end: Beats,
+    (time_sig_num, time_sig_den): (u8, u8),
+    unmetered: bool,
+    warnings: &mut Vec<String>,
+) -> f64 {
+    let duration = end - start;
+    if unmetered || time_sig_den == 0 {
+        return duration.to_f64();
+    }
+    let bar = Beats::new(time_sig_num as i64 * 4, time_sig_den as i64);
+    if duration > bar {
+        warnings.push(format!(
+            "Measure {}: contents last {} beats, longer than the {} its {}/{} time signature allows",
+            number,
+            duration.to_f64(),
+            bar.to_f64(),
+            time_sig_num,
+            time_sig_den
+        ));
+    }
+    duration.to_f64()
+}
+
+/// The finished measure `number`, spanning `start` to `end`.
+fn finish_measure(
+    number: u32,
+    start: Beats,
+    end: Beats,
+    time_sig: (u8, u8),
+    unmetered: bool,
+    clef: &Clef,
+    warnings: &mut Vec<String>,
+) -> MeasureInfo {
+    MeasureInfo {
+        number,
+        start_beat: start.to_f64(),
+        duration_beats: measure_duration(number, start, end, time_sig, unmetered, warnings),
+        time_sig_num: time_sig.0,
+        time_sig_den: time_sig.1,
+        clef: clef.clone(),
+        unmetered,
+    }
+}
+
+/// Upper bound on XML events read by `parse_musicxml`, so malformed input
+/// can never spin the (single) WASM thread indefinitely.
+pub const DEFAULT_MAX_EVENTS: usize = 5_000_000;
+
 pub fn parse_musicxml(xml: &str) -> Result<Score, String> {
+    parse_musicxml_with_limit(xml, DEFAULT_MAX_EVENTS)
+}
+
+/// Parse MusicXML, aborting with an error after `max_events` XML events.
+pub fn parse_musicxml_with_limit(xml: &str, max_events: usize) -> Result<Score, String> {
+    parse_events(xml, max_events, None).map(|(score, _)| score)
+}
+
+/// Parse MusicXML, also returning warnings about input that was repaired
+/// rather than rejected (e.g. a zero-length note given its type's length).
+pub fn parse_musicxml_with_warnings(xml: &str) -> Result<(Score, Vec<String>), String> {
+    parse_events(xml, DEFAULT_MAX_EVENTS, None)
+}
+
+/// Parse only the `<part>` with id `part_id` (e.g. the trumpet line of a
+/// band score), using that part's own attributes and instrument name.
+pub fn parse_musicxml_part(xml: &str, part_id: &str) -> Result<Score, String> {
+    parse_events(xml, DEFAULT_MAX_EVENTS, Some(part_id)).map(|(score, _)| score)
+}
+
+fn parse_events(
+    xml: &str,
+    max_events: usize,
+    part: Option<&str>,
+) -> Result<(Score, Vec<String>), String> {
     let mut reader = Reader::from_str(xml);
     reader.trim_text(true);
 
     let mut buf = Vec::new();
+    let mut event_count: usize = 0;
+    let mut warnings: Vec<String> = Vec::new();
 
-    let mut divisions: f64 = 1.0;
+    let mut divisions = Beats::new(1, 1);
     let mut tempo: f64 = 120.0;
     let mut notes: Vec<NoteEvent> = Vec::new();
     let mut measures: Vec<MeasureInfo> = Vec::new();
 
-    let mut current_beat: f64 = 0.0;
-    let mut last_note_start: f64 = 0.0;
-    let mut last_note_duration: f64 = 0.0;
+    let mut current_beat = Beats::ZERO;
+    let mut last_note_start = Beats::ZERO;
+    let mut last_note_duration = Beats::ZERO;
 
     let mut current_tag: Option<&'static str> = None;
 
@@ -38,15 +382,38 @@ pub fn parse_musicxml(xml: &str) -> Result<Score, String> {
     let mut in_note = false;
     let mut note_is_rest = false;
     let mut note_is_chord = false;
-    let mut note_duration_divs: Option<f64> = None;
+    let mut note_is_grace = false;
+    let mut note_dots: u32 = 0;
+    let mut note_duration_divs: Option<Beats> = None;
     let mut note_type_str: String = String::new();
     let mut step: Option<char> = None;
-    let mut alter: i32 = 0;
+    let mut alter: f64 = 0.0;
     let mut octave: Option<i32> = None;
+    let mut note_articulations: Vec<String> = Vec::new();
+    let mut in_articulations = false;
+    let mut note_ornaments: Vec<String> = Vec::new();
+    let mut note_accidental: Option<String> = None;
+    let mut note_glissando = false;
+
+    // <backup>/<forward> between notes (other voices, hidden rests): which
+    // one is open, and its <duration>
+    let mut in_time_shift: Option<&'static str> = None;
+    let mut shift_divs: Option<Beats> = None;
+    let mut in_ornaments = false;
+    let mut note_beam_group: Option<u32> = None;
+
+    // Beam state: id of the most recent primary beam group
+    let mut beam_group_count: u32 = 0;
 
     // Measure state
     let mut current_measure_number: u32 = 0;
-    let mut measure_start_beat: f64 = 0.0;
+    let mut measure_start_beat = Beats::ZERO;
+    // Whether a non-chord note has started in the current measure. A
+    // `<chord/>` note before any such onset has nothing to stack on.
+    let mut measure_has_onset = false;
+    // Furthest beat reached in the current measure, which a <backup> to
+    // write another voice leaves behind
+    let mut measure_end_beat = Beats::ZERO;
 
     // Score-level metadata
     let mut key_fifths: i32 = 0;
@@ -59,25 +426,90 @@ pub fn parse_musicxml(xml: &str) -> Result<Score, String> {
     let mut in_transpose = false;
     let mut transpose_chromatic: i32 = 0;
     let mut transpose_diatonic: i32 = 0;
+    // First (or selected) part's names, used to infer a missing <transpose>
+    let mut instrument_name: Option<String> = None;
+    let mut part_name: Option<String> = None;
+
+    // Part selection: the <score-part> being read in the part list, and
+    // whether the current <part> is one being skipped
+    let mut score_part_id: Option<String> = None;
+    let mut skipping_part = false;
+    let mut found_part = false;
+
+    // Dynamics: the most recent marking stays in effect for following notes
+    let mut current_dynamics: Option<String> = None;
+    let mut accent_dynamics: Option<String> = None;
+    let mut in_dynamics = false;
+    // Playback velocity from <sound dynamics="...">, in effect until changed
+    let mut current_velocity: Option<u8> = None;
+    // Hairpins: closed spans, and open ones as (number, direction, start)
+    let mut wedges: Vec<WedgeSpan> = Vec::new();
+    let mut open_wedges: Vec<(String, String, Beats)> = Vec::new();
+
+    // Senza misura: set by <senza-misura/> and until the part's first
+    // <time>, cleared by a metered <time>
+    let mut unmetered = true;
+
+    // Clef state
+    let mut clef = Clef::treble();
+    let mut in_clef = false;
 
     // Tag context tracking
     let mut in_type_tag = false;
 
     loop {
-        match reader.read_event_into(&mut buf) {
+        event_count += 1;
+        if event_count > max_events {
+            return Err(format!(
+                "XML parse aborted: exceeded {} events (malformed or oversized input?)",
+                max_events
+            ));
+        }
+        let event = reader.read_event_into(&mut buf);
+        if skipping_part {
+            if matches!(&event, Ok(Event::End(e)) if e.name().as_ref() == b"part") {
+                skipping_part = false;
+            }
+            if !matches!(event, Ok(Event::Eof) | Err(_)) {
+                buf.clear();
+                continue;
+            }
+        }
+        match event {
             Ok(Event::Start(ref e)) => {
                 let name = e.name();
                 match name.as_ref() {
+                    b"score-part" => score_part_id = text_attr(e, b"id"),
+                    b"part" => {
+                        let id = text_attr(e, b"id");
+                        skipping_part = part.is_some_and(|p| id.as_deref() != Some(p));
+                        if !skipping_part {
+                            found_part = true;
+                            // Attributes belong to their part; don't carry
+                            // an earlier part's over into this one
+                            divisions = Beats::new(1, 1);
+                            time_sig_num = 4;
+                            time_sig_den = 4;
+                            key_fifths = 0;
+                            unmetered = true;
+                            clef = Clef::treble();
+                        }
+                    }
                     b"measure" => {
                         // Finalize previous measure if any
                         if current_measure_number > 0 {
-                            measures.push(MeasureInfo {
-                                number: current_measure_number,
-                                start_beat: measure_start_beat,
-                                duration_beats: current_beat - measure_start_beat,
-                                time_sig_num,
-                                time_sig_den,
-                            });
+                            if current_beat < measure_end_beat {
+                                current_beat = measure_end_beat;
+                            }
+                            measures.push(finish_measure(
+                                current_measure_number,
+                                measure_start_beat,
+                                current_beat,
+                                (time_sig_num, time_sig_den),
+                                unmetered,
+                                &clef,
+                                &mut warnings,
+                            ));
                         }
                         // Parse measure number attribute
                         if let Some(attr) = e
@@ -92,111 +524,205 @@ pub fn parse_musicxml(xml: &str) -> Result<Score, String> {
                             }
                         }
                         measure_start_beat = current_beat;
+                        measure_end_beat = current_beat;
+                        measure_has_onset = false;
                     }
                     b"note" => {
                         in_note = true;
                         note_is_rest = false;
                         note_is_chord = false;
+                        note_is_grace = false;
+                        note_dots = 0;
                         note_duration_divs = None;
                         note_type_str.clear();
                         step = None;
-                        alter = 0;
+                        alter = 0.0;
                         octave = None;
+                        note_articulations.clear();
+                        note_ornaments.clear();
+                        note_accidental = None;
+                        note_glissando = false;
+                        note_beam_group = None;
                     }
-                    b"rest" => {
-                        if in_note {
-                            note_is_rest = true;
-                        }
+                    b"articulations" if in_note => in_articulations = true,
+                    b"ornaments" if in_note => in_ornaments = true,
+                    b"dynamics" => in_dynamics = true,
+                    b"other-dynamics" if in_dynamics => current_tag = Some("other-dynamics"),
+                    other if in_articulations => {
+                        note_articulations.push(String::from_utf8_lossy(other).into_owned());
                     }
-                    b"chord" => {
-                        if in_note {
-                            note_is_chord = true;
+                    other if in_ornaments => {
+                        let mark = String::from_utf8_lossy(other);
+                        if ORNAMENT_MARKS.contains(&mark.as_ref()) {
+                            note_ornaments.push(mark.into_owned());
                         }
                     }
+                    b"slide" | b"glissando" if in_note => {
+                        note_glissando |= text_attr(e, b"type").as_deref() == Some("start");
+                    }
+                    b"rest" if in_note => {
+                        note_is_rest = true;
+                    }
+                    b"chord" if in_note => {
+                        note_is_chord = true;
+                    }
+                    b"grace" if in_note => {
+                        note_is_grace = true;
+                    }
                     b"transpose" => {
                         in_transpose = true;
                         transpose_chromatic = 0;
                         transpose_diatonic = 0;
                     }
+                    b"clef" => {
+                        in_clef = true;
+                        clef = Clef {
+                            sign: String::new(),
+                            line: 0,
+                            octave_change: 0,
+                        };
+                    }
+                    b"sign" => current_tag = Some("sign"),
+                    b"line" => current_tag = Some("line"),
+                    b"clef-octave-change" => current_tag = Some("clef-octave-change"),
                     b"divisions" => current_tag = Some("divisions"),
+                    b"backup" | b"forward" if !in_note => {
+                        in_time_shift = Some(if name.as_ref() == b"backup" {
+                            "backup"
+                        } else {
+                            "forward"
+                        });
+                        shift_divs = None;
+                    }
                     b"duration" => current_tag = Some("duration"),
                     b"step" => current_tag = Some("step"),
                     b"alter" => current_tag = Some("alter"),
                     b"octave" => current_tag = Some("octave"),
                     b"per-minute" => current_tag = Some("per-minute"),
                     b"fifths" => current_tag = Some("fifths"),
-                    b"beats" => current_tag = Some("beats"),
+                    b"beats" => {
+                        unmetered = false;
+                        current_tag = Some("beats");
+                    }
+                    b"senza-misura" => unmetered = true,
                     b"beat-type" => current_tag = Some("beat-type"),
                     b"chromatic" => current_tag = Some("chromatic"),
                     b"diatonic" => current_tag = Some("diatonic"),
                     b"movement-title" => current_tag = Some("movement-title"),
                     b"work-title" => current_tag = Some("work-title"),
-                    b"type" => {
-                        if in_note {
-                            in_type_tag = true;
-                            current_tag = Some("type");
+                    b"part-name" => current_tag = Some("part-name"),
+                    b"instrument-name" => current_tag = Some("instrument-name"),
+                    b"beam" if in_note => {
+                        // Only the primary beam (number="1", the default) groups notes
+                        let primary = e
+                            .attributes()
+                            .flatten()
+                            .find(|a| a.key.as_ref() == b"number")
+                            .is_none_or(|a| a.value.as_ref() == b"1");
+                        if primary {
+                            current_tag = Some("beam");
                         }
                     }
+                    b"accidental" if in_note => current_tag = Some("accidental"),
+                    b"type" if in_note => {
+                        in_type_tag = true;
+                        current_tag = Some("type");
+                    }
                     b"sound" => {
-                        if let Some(attr) =
-                            e.attributes().flatten().find(|a| a.key.as_ref() == b"tempo")
-                        {
-                            if let Ok(val) = std::str::from_utf8(&attr.value) {
-                                if let Ok(t) = val.parse::<f64>() {
-                                    tempo = t;
-                                }
-                            }
+                        if let Some(t) = numeric_attr(e, b"tempo") {
+                            set_tempo(t, &mut tempo, current_measure_number, &mut warnings);
+                        }
+                        if let Some(d) = numeric_attr(e, b"dynamics") {
+                            current_velocity = Some(sound_velocity(d));
                         }
                     }
+                    b"wedge" => wedge_event(e, current_beat, &mut open_wedges, &mut wedges),
                     _ => {}
                 }
             }
             Ok(Event::Empty(ref e)) => {
                 let name = e.name();
                 if name.as_ref() == b"sound" {
-                    if let Some(attr) =
-                        e.attributes().flatten().find(|a| a.key.as_ref() == b"tempo")
-                    {
-                        if let Ok(val) = std::str::from_utf8(&attr.value) {
-                            if let Ok(t) = val.parse::<f64>() {
-                                tempo = t;
-                            }
-                        }
+                    if let Some(t) = numeric_attr(e, b"tempo") {
+                        set_tempo(t, &mut tempo, current_measure_number, &mut warnings);
+                    }
+                    if let Some(d) = numeric_attr(e, b"dynamics") {
+                        current_velocity = Some(sound_velocity(d));
                     }
                 }
+                if name.as_ref() == b"wedge" {
+                    wedge_event(e, current_beat, &mut open_wedges, &mut wedges);
+                }
                 if name.as_ref() == b"rest" && in_note {
                     note_is_rest = true;
                 }
                 if name.as_ref() == b"chord" && in_note {
                     note_is_chord = true;
                 }
+                if name.as_ref() == b"grace" && in_note {
+                    note_is_grace = true;
+                }
+                if name.as_ref() == b"dot" && in_note {
+                    note_dots += 1;
+                }
+                if matches!(name.as_ref(), b"slide" | b"glissando") && in_note {
+                    note_glissando |= text_attr(e, b"type").as_deref() == Some("start");
+                }
+                if in_dynamics {
+                    let marking = String::from_utf8_lossy(name.as_ref()).into_owned();
+                    set_dynamics(marking, &mut current_dynamics, &mut accent_dynamics);
+                }
+                if name.as_ref() == b"senza-misura" {
+                    unmetered = true;
+                }
+                if in_articulations {
+                    note_articulations.push(String::from_utf8_lossy(name.as_ref()).into_owned());
+                }
+                if in_ornaments {
+                    let mark = String::from_utf8_lossy(name.as_ref());
+                    if ORNAMENT_MARKS.contains(&mark.as_ref()) {
+                        note_ornaments.push(mark.into_owned());
+                    }
+                }
             }
             Ok(Event::Text(e)) => {
                 if let Some(tag) = current_tag.take() {
                     let text = e.unescape().map_err(|e| e.to_string())?;
+                    let in_selected_score_part =
+                        part.is_none_or(|p| score_part_id.as_deref() == Some(p));
                     match tag {
-                        "divisions" => {
-                            if let Ok(v) = text.parse::<f64>() {
-                                if v > 0.0 {
-                                    divisions = v;
-                                }
-                            }
-                        }
+                        "divisions" => match Beats::parse(&text) {
+                            Some(v) if v.num > 0 => divisions = v,
+                            _ => warnings.push(format!(
+                                "Measure {}: ignoring invalid divisions {:?}; keeping {}",
+                                current_measure_number,
+                                text.trim(),
+                                divisions.to_f64()
+                            )),
+                        },
                         "per-minute" => {
-                            if let Ok(v) = text.parse::<f64>() {
-                                tempo = v;
+                            if let Ok(v) = text.trim().parse::<f64>() {
+                                set_tempo(v, &mut tempo, current_measure_number, &mut warnings);
                             }
                         }
+                        "duration" if in_time_shift.is_some() => {
+                            shift_divs = Beats::parse(&text);
+                        }
                         "duration" => {
-                            if let Ok(v) = text.parse::<f64>() {
-                                note_duration_divs = Some(v);
-                            }
+                            note_duration_divs = Beats::parse(&text);
+                        }
+                        "accidental" => {
+                            note_accidental = Some(text.trim().to_string());
+                        }
+                        "other-dynamics" => {
+                            let marking = text.trim().to_string();
+                            set_dynamics(marking, &mut current_dynamics, &mut accent_dynamics);
                         }
                         "step" => {
                             step = text.chars().next();
                         }
                         "alter" => {
-                            if let Ok(v) = text.parse::<i32>() {
+                            if let Ok(v) = text.parse::<f64>() {
                                 alter = v;
                             }
                         }
@@ -220,30 +746,51 @@ pub fn parse_musicxml(xml: &str) -> Result<Score, String> {
                                 time_sig_den = v;
                             }
                         }
-                        "chromatic" => {
-                            if in_transpose {
-                                if let Ok(v) = text.parse::<i32>() {
-                                    transpose_chromatic = v;
-                                }
+                        "chromatic" if in_transpose => {
+                            if let Ok(v) = text.parse::<i32>() {
+                                transpose_chromatic = v;
                             }
                         }
-                        "diatonic" => {
-                            if in_transpose {
-                                if let Ok(v) = text.parse::<i32>() {
-                                    transpose_diatonic = v;
-                                }
+                        "diatonic" if in_transpose => {
+                            if let Ok(v) = text.parse::<i32>() {
+                                transpose_diatonic = v;
+                            }
+                        }
+                        "sign" if in_clef => {
+                            clef.sign = text.to_string();
+                        }
+                        "line" if in_clef => {
+                            if let Ok(v) = text.parse::<u8>() {
+                                clef.line = v;
                             }
                         }
-                        "type" => {
-                            if in_type_tag {
-                                note_type_str = text.to_string();
-                                in_type_tag = false;
+                        "clef-octave-change" if in_clef => {
+                            if let Ok(v) = text.parse::<i32>() {
+                                clef.octave_change = v;
                             }
                         }
-                        "movement-title" | "work-title" => {
-                            if title.is_none() {
-                                title = Some(text.to_string());
+                        "beam" => {
+                            if text.as_ref() == "begin" {
+                                beam_group_count += 1;
                             }
+                            if beam_group_count > 0 {
+                                note_beam_group = Some(beam_group_count);
+                            }
+                        }
+                        "type" if in_type_tag => {
+                            note_type_str = text.to_string();
+                            in_type_tag = false;
+                        }
+                        "movement-title" | "work-title" if title.is_none() => {
+                            title = Some(text.to_string());
+                        }
+                        "part-name" if part_name.is_none() && in_selected_score_part => {
+                            part_name = Some(text.to_string());
+                        }
+                        "instrument-name"
+                            if instrument_name.is_none() && in_selected_score_part =>
+                        {
+                            instrument_name = Some(text.to_string());
                         }
                         _ => {}
                     }
@@ -253,50 +800,95 @@ pub fn parse_musicxml(xml: &str) -> Result<Score, String> {
                 let name = e.name();
                 match name.as_ref() {
                     b"note" if in_note => {
-                        let duration_divs = note_duration_divs.unwrap_or(0.0);
-                        let duration_beats = if divisions > 0.0 {
-                            duration_divs / divisions
-                        } else {
-                            0.0
-                        };
+                        let mut duration_beats = note_duration_divs.unwrap_or(Beats::ZERO) / divisions;
+
+                        // Grace notes take no time; any other zero-length note
+                        // would silently vanish, so fall back to its <type>.
+                        if duration_beats.is_zero() && !note_is_grace {
+                            match note_type_beats(&note_type_str, note_dots) {
+                                Some(implied) => {
+                                    warnings.push(format!(
+                                        "Measure {}: {} note has zero duration; using {} beats from its type",
+                                        current_measure_number,
+                                        note_type_str,
+                                        implied.to_f64()
+                                    ));
+                                    duration_beats = implied;
+                                }
+                                None => warnings.push(format!(
+                                    "Measure {}: note has zero duration and no usable <type>",
+                                    current_measure_number
+                                )),
+                            }
+                        }
 
+                        // A stray chord flag on the first note of a measure would
+                        // otherwise inherit the previous measure's onset.
+                        let note_is_chord = note_is_chord && measure_has_onset;
                         let start_beat = if note_is_chord {
                             last_note_start
                         } else {
                             current_beat
                         };
 
+                        // Fractional alters (quarter tones) round to the nearest
+                        // semitone; the remainder is kept as a cents offset.
+                        let semitone_alter = alter.round();
+                        let microtone_cents = if note_is_rest {
+                            0.0
+                        } else {
+                            (alter - semitone_alter) * 100.0
+                        };
                         let midi = if note_is_rest {
                             -1
                         } else {
                             let s = step.ok_or("Missing pitch step")?;
                             let o = octave.ok_or("Missing pitch octave")?;
-                            midi_from_pitch(s, alter, o)
+                            midi_from_pitch(s, semitone_alter as i32, o)
                         };
 
                         notes.push(NoteEvent {
-                            start_beat,
-                            duration_beats,
+                            start_beat: start_beat.to_f64(),
+                            duration_beats: duration_beats.to_f64(),
                             midi,
                             is_rest: note_is_rest,
                             measure_number: current_measure_number,
                             note_type: if note_type_str.is_empty() {
-                                "quarter".to_string()
+                                beats_to_note_type(duration_beats.to_f64()).to_string()
                             } else {
                                 note_type_str.clone()
                             },
+                            microtone_cents,
+                            articulations: note_articulations.clone(),
+                            beam_group: note_beam_group,
+                            dynamics: accent_dynamics.take().or_else(|| current_dynamics.clone()),
+                            velocity: current_velocity,
+                            ornaments: note_ornaments.clone(),
+                            accidental: note_accidental.take(),
+                            glissando_to: note_glissando,
+                            accompaniment: false,
                         });
 
                         if !note_is_chord {
+                            measure_has_onset = true;
                             last_note_start = start_beat;
                             last_note_duration = duration_beats;
-                            current_beat += duration_beats;
-                        } else if last_note_duration == 0.0 {
+                            current_beat = current_beat + duration_beats;
+                        } else if last_note_duration.is_zero() {
                             last_note_duration = duration_beats;
                         }
 
                         in_note = false;
                     }
+                    b"articulations" => {
+                        in_articulations = false;
+                    }
+                    b"ornaments" => {
+                        in_ornaments = false;
+                    }
+                    b"dynamics" => {
+                        in_dynamics = false;
+                    }
                     b"transpose" => {
                         in_transpose = false;
                         transpose = Some(TransposeInfo {
@@ -304,9 +896,55 @@ pub fn parse_musicxml(xml: &str) -> Result<Score, String> {
                             diatonic: transpose_diatonic,
                         });
                     }
+                    b"clef" => {
+                        in_clef = false;
+                        // Clefs without an explicit line use the standard one
+                        if clef.line == 0 {
+                            clef.line = match clef.sign.as_str() {
+                                "F" => 4,
+                                "C" => 3,
+                                _ => 2,
+                            };
+                        }
+                    }
                     b"type" => {
                         in_type_tag = false;
                     }
+                    b"backup" | b"forward" => {
+                        let shift = shift_divs.take().unwrap_or(Beats::ZERO) / divisions;
+                        if in_time_shift.take() == Some("forward") {
+                            current_beat = current_beat + shift;
+                        } else {
+                            if measure_end_beat < current_beat {
+                                measure_end_beat = current_beat;
+                            }
+                            current_beat = current_beat - shift;
+                            if current_beat < measure_start_beat {
+                                warnings.push(format!(
+                                    "Measure {}: <backup> goes past the start of the measure; stopping at the barline",
+                                    current_measure_number
+                                ));
+                                current_beat = measure_start_beat;
+                            }
+                        }
+                    }
+                    // Close the part's last measure under its own attributes,
+                    // before the next part resets them
+                    b"part" if current_measure_number > 0 => {
+                        if current_beat < measure_end_beat {
+                            current_beat = measure_end_beat;
+                        }
+                        measures.push(finish_measure(
+                            current_measure_number,
+                            measure_start_beat,
+                            current_beat,
+                            (time_sig_num, time_sig_den),
+                            unmetered,
+                            &clef,
+                            &mut warnings,
+                        ));
+                        current_measure_number = 0;
+                    }
                     _ => {}
                 }
             }
@@ -317,20 +955,61 @@ pub fn parse_musicxml(xml: &str) -> Result<Score, String> {
         buf.clear();
     }
 
+    if let Some(id) = part.filter(|_| !found_part) {
+        return Err(format!("Part {} not found", id));
+    }
+
     // Finalize the last measure
     if current_measure_number > 0 {
-        measures.push(MeasureInfo {
-            number: current_measure_number,
-            start_beat: measure_start_beat,
-            duration_beats: current_beat - measure_start_beat,
-            time_sig_num,
-            time_sig_den,
+        if current_beat < measure_end_beat {
+            current_beat = measure_end_beat;
+        }
+        measures.push(finish_measure(
+            current_measure_number,
+            measure_start_beat,
+            current_beat,
+            (time_sig_num, time_sig_den),
+            unmetered,
+            &clef,
+            &mut warnings,
+        ));
+    }
+
+    // Time only advances on non-chord notes, so a final chord member held
+    // longer than the note it stacks on would otherwise be cut off.
+    let total_beats = notes
+        .iter()
+        .map(|n| n.start_beat + n.duration_beats)
+        .fold(current_beat.to_f64(), f64::max);
+
+    // A hairpin never stopped runs to the end of the score
+    for (_, direction, start) in open_wedges {
+        warnings.push(format!(
+            "{} starting at beat {} has no stop; extended to the end",
+            direction,
+            start.to_f64()
+        ));
+        wedges.push(WedgeSpan {
+            start_beat: start.to_f64(),
+            end_beat: total_beats,
+            direction,
         });
     }
+    wedges.sort_by(|a, b| {
+        a.start_beat
+            .partial_cmp(&b.start_beat)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
 
-    let total_beats = current_beat;
+    // Without an explicit <transpose>, fall back on the instrument's name
+    let transpose = transpose.or_else(|| {
+        instrument_name
+            .as_deref()
+            .and_then(transpose_for_instrument)
+            .or_else(|| part_name.as_deref().and_then(transpose_for_instrument))
+    });
 
-    Ok(Score {
+    let score = Score {
         tempo,
         notes,
         measures,
@@ -338,7 +1017,9 @@ pub fn parse_musicxml(xml: &str) -> Result<Score, String> {
         transpose,
         title,
         total_beats,
-    })
+        wedges,
+    };
+    Ok((score, warnings))
 }
 
 #[cfg(test)]
@@ -462,6 +1143,33 @@ mod tests {
         assert_eq!(t.diatonic, -1);
     }
 
+    #[test]
+    fn test_parse_transpose_from_part_name() {
+        let xml = |part: &str, transpose: &str| {
+            format!(
+                r#"<score-partwise version="3.1">
+                <part-list><score-part id="P1"><part-name>{}</part-name>
+                    <score-instrument id="P1-I1"><instrument-name>Trumpet</instrument-name></score-instrument>
+                </score-part></part-list>
+                <part id="P1"><measure number="1"><attributes><divisions>1</divisions>{}</attributes>
+                <note><pitch><step>C</step><octave>5</octave></pitch><duration>4</duration></note>
+                </measure></part></score-partwise>"#,
+                part, transpose
+            )
+        };
+
+        let score = parse_musicxml(&xml("Trumpet in Bb", "")).unwrap();
+        let transpose = score.transpose.unwrap();
+        assert_eq!((transpose.chromatic, transpose.diatonic), (-2, -1));
+
+        // An explicit <transpose> wins over the name
+        let explicit = "<transpose><diatonic>0</diatonic><chromatic>0</chromatic></transpose>";
+        let score = parse_musicxml(&xml("Trumpet in Bb", explicit)).unwrap();
+        assert_eq!(score.transpose.unwrap().chromatic, 0);
+
+        assert!(parse_musicxml(&xml("Violin", "")).unwrap().transpose.is_none());
+    }
+
     #[test]
     fn test_parse_multiple_measures() {
         let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
@@ -509,6 +1217,751 @@ mod tests {
         assert_eq!(score.notes[1].measure_number, 2);
     }
 
+    #[test]
+    fn test_parse_chord_first_in_measure() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<score-partwise version="3.1">
+  <part-list><score-part id="P1"><part-name>Trumpet</part-name></score-part></part-list>
+  <part id="P1">
+    <measure number="1">
+      <attributes>
+        <divisions>1</divisions>
+        <time><beats>4</beats><beat-type>4</beat-type></time>
+      </attributes>
+      <note>
+        <pitch><step>G</step><octave>4</octave></pitch>
+        <duration>4</duration>
+        <type>whole</type>
+      </note>
+    </measure>
+    <measure number="2">
+      <note>
+        <pitch><step>C</step><octave>4</octave></pitch>
+        <duration>2</duration>
+        <type>half</type>
+      </note>
+      <note>
+        <chord/>
+        <pitch><step>E</step><octave>4</octave></pitch>
+        <duration>2</duration>
+        <type>half</type>
+      </note>
+      <note>
+        <chord/>
+        <pitch><step>G</step><octave>4</octave></pitch>
+        <duration>2</duration>
+        <type>half</type>
+      </note>
+      <note>
+        <pitch><step>D</step><octave>4</octave></pitch>
+        <duration>2</duration>
+        <type>half</type>
+      </note>
+    </measure>
+    <measure number="3">
+      <note>
+        <chord/>
+        <pitch><step>E</step><octave>4</octave></pitch>
+        <duration>4</duration>
+        <type>whole</type>
+      </note>
+    </measure>
+  </part>
+</score-partwise>"#;
+
+        let score = parse_musicxml(xml).unwrap();
+        assert_eq!(score.notes.len(), 6);
+
+        // Chord opening measure 2 stacks on the measure's first onset
+        for note in &score.notes[1..4] {
+            assert_eq!(note.start_beat, 4.0);
+            assert_eq!(note.measure_number, 2);
+        }
+        assert_eq!(score.notes[4].start_beat, 6.0);
+
+        // A stray <chord/> on a measure's first note starts its own onset
+        assert_eq!(score.notes[5].start_beat, 8.0);
+        assert_eq!(score.measures[2].duration_beats, 4.0);
+        assert_eq!(score.total_beats, 12.0);
+    }
+
+    #[test]
+    fn test_parse_backup_and_forward() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<score-partwise version="3.1">
+  <part id="P1">
+    <measure number="1">
+      <attributes>
+        <divisions>2</divisions>
+        <time><beats>4</beats><beat-type>4</beat-type></time>
+      </attributes>
+      <note><pitch><step>G</step><octave>4</octave></pitch><duration>8</duration><voice>1</voice></note>
+      <backup><duration>8</duration></backup>
+      <note><pitch><step>C</step><octave>4</octave></pitch><duration>4</duration><voice>2</voice></note>
+      <note><pitch><step>E</step><octave>4</octave></pitch><duration>2</duration><voice>2</voice></note>
+    </measure>
+    <measure number="2">
+      <forward><duration>4</duration></forward>
+      <note><pitch><step>D</step><octave>4</octave></pitch><duration>4</duration></note>
+      <backup><duration>12</duration></backup>
+      <note><pitch><step>F</step><octave>4</octave></pitch><duration>2</duration></note>
+    </measure>
+  </part>
+</score-partwise>"#;
+
+        let (score, warnings) = parse_musicxml_with_warnings(xml).unwrap();
+        let starts: Vec<f64> = score.notes.iter().map(|n| n.start_beat).collect();
+        assert_eq!(starts, vec![0.0, 0.0, 2.0, 6.0, 4.0]);
+
+        // Voice 2 stops short of the barline; the measure still spans voice 1
+        assert_eq!(score.measures[0].duration_beats, 4.0);
+        assert_eq!(score.measures[1].start_beat, 4.0);
+        assert_eq!(score.measures[1].duration_beats, 4.0);
+        assert_eq!(score.total_beats, 8.0);
+
+        // A backup past the barline stops there rather than reaching into measure 1
+        assert_eq!(score.notes[4].measure_number, 2);
+        assert!(warnings.iter().any(|w| w.contains("Measure 2: <backup>")));
+    }
+
+    #[test]
+    fn test_parse_total_beats_ends_on_chord() {
+        let chord_ending = |top_duration: u32| {
+            format!(
+                r#"<score-partwise version="3.1"><part id="P1"><measure number="1">
+                <attributes><divisions>1</divisions></attributes>
+                <note><pitch><step>C</step><octave>4</octave></pitch><duration>2</duration></note>
+                <note><pitch><step>E</step><octave>4</octave></pitch><duration>2</duration></note>
+                <note><chord/><pitch><step>G</step><octave>4</octave></pitch><duration>{}</duration></note>
+                </measure></part></score-partwise>"#,
+                top_duration
+            )
+        };
+
+        // Chord members are not double-counted
+        let score = parse_musicxml(&chord_ending(2)).unwrap();
+        assert_eq!(score.notes[2].start_beat, 2.0);
+        assert_eq!(score.total_beats, 4.0);
+
+        // A longer final chord member extends the piece
+        let score = parse_musicxml(&chord_ending(4)).unwrap();
+        assert_eq!(score.total_beats, 6.0);
+        assert_eq!(score.measures[0].duration_beats, 4.0);
+    }
+
+    #[test]
+    fn test_parse_microtonal_alter() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<score-partwise version="3.1">
+  <part-list><score-part id="P1"><part-name>Trumpet</part-name></score-part></part-list>
+  <part id="P1">
+    <measure number="1">
+      <attributes><divisions>1</divisions></attributes>
+      <note>
+        <pitch><step>C</step><alter>0.5</alter><octave>4</octave></pitch>
+        <duration>1</duration>
+        <type>quarter</type>
+      </note>
+      <note>
+        <pitch><step>B</step><alter>-1</alter><octave>4</octave></pitch>
+        <duration>1</duration>
+        <type>quarter</type>
+      </note>
+    </measure>
+  </part>
+</score-partwise>"#;
+
+        let score = parse_musicxml(xml).unwrap();
+        assert_eq!(score.notes[0].midi, 61);
+        assert_eq!(score.notes[0].microtone_cents, -50.0);
+        assert_eq!(score.notes[0].target_pitch(), 60.5);
+        assert_eq!(score.notes[1].midi, 70);
+        assert_eq!(score.notes[1].microtone_cents, 0.0);
+    }
+
+    #[test]
+    fn test_parse_clef() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<score-partwise version="3.1">
+  <part-list><score-part id="P1"><part-name>Trumpet</part-name></score-part></part-list>
+  <part id="P1">
+    <measure number="1">
+      <attributes>
+        <divisions>1</divisions>
+        <clef><sign>G</sign><line>2</line></clef>
+      </attributes>
+      <note>
+        <pitch><step>C</step><octave>4</octave></pitch>
+        <duration>4</duration>
+        <type>whole</type>
+      </note>
+    </measure>
+    <measure number="2">
+      <attributes>
+        <clef><sign>G</sign><line>2</line><clef-octave-change>-1</clef-octave-change></clef>
+      </attributes>
+      <note>
+        <pitch><step>C</step><octave>4</octave></pitch>
+        <duration>4</duration>
+        <type>whole</type>
+      </note>
+    </measure>
+    <measure number="3">
+      <note>
+        <pitch><step>C</step><octave>4</octave></pitch>
+        <duration>4</duration>
+        <type>whole</type>
+      </note>
+    </measure>
+  </part>
+</score-partwise>"#;
+
+        let score = parse_musicxml(xml).unwrap();
+        assert_eq!(score.measures[0].clef, Clef::treble());
+        let treble_8vb = Clef {
+            sign: "G".to_string(),
+            line: 2,
+            octave_change: -1,
+        };
+        assert_eq!(score.measures[1].clef, treble_8vb);
+        // Clef carries forward until changed
+        assert_eq!(score.measures[2].clef, treble_8vb);
+    }
+
+    #[test]
+    fn test_parse_event_limit() {
+        // Deeply nested and never closed
+        let xml = format!(
+            "<score-partwise>{}",
+            "<part><measure><note>".repeat(10_000)
+        );
+        let err = parse_musicxml_with_limit(&xml, 1_000).unwrap_err();
+        assert!(err.contains("exceeded 1000 events"), "{}", err);
+
+        // Truncated input stops at EOF under the default limit, keeping only
+        // what was complete: the unfinished notes are dropped
+        let score = parse_musicxml(&xml).unwrap();
+        assert!(score.notes.is_empty());
+        let score =
+            parse_musicxml("<score-partwise><part><measure number=\"1\"><note><pitch><st").unwrap();
+        assert!(score.notes.is_empty());
+        assert_eq!(score.measures.len(), 1);
+        assert_eq!(score.measures[0].number, 1);
+    }
+
+    #[test]
+    fn test_parse_articulations() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<score-partwise version="3.1">
+  <part-list><score-part id="P1"><part-name>Trumpet</part-name></score-part></part-list>
+  <part id="P1">
+    <measure number="1">
+      <attributes><divisions>1</divisions></attributes>
+      <note>
+        <pitch><step>C</step><octave>4</octave></pitch>
+        <duration>1</duration>
+        <type>quarter</type>
+        <notations><articulations><staccato placement="above"/><accent/></articulations></notations>
+      </note>
+      <note>
+        <pitch><step>D</step><octave>4</octave></pitch>
+        <duration>1</duration>
+        <type>quarter</type>
+      </note>
+    </measure>
+  </part>
+</score-partwise>"#;
+
+        let score = parse_musicxml(xml).unwrap();
+        assert_eq!(score.notes[0].articulations, vec!["staccato", "accent"]);
+        assert!(score.notes[1].articulations.is_empty());
+    }
+
+    #[test]
+    fn test_parse_ornaments() {
+        let xml = r#"<score-partwise version="3.1"><part id="P1"><measure number="1">
+            <attributes><divisions>1</divisions></attributes>
+            <note><pitch><step>C</step><octave>5</octave></pitch><duration>2</duration>
+                <notations><ornaments><trill-mark/><wavy-line type="start"/></ornaments></notations></note>
+            <note><pitch><step>D</step><octave>5</octave></pitch><duration>1</duration>
+                <notations><ornaments><inverted-mordent/><accidental-mark>sharp</accidental-mark></ornaments>
+                <articulations><staccato/></articulations></notations></note>
+            <note><pitch><step>E</step><octave>5</octave></pitch><duration>1</duration></note>
+        </measure></part></score-partwise>"#;
+
+        let score = parse_musicxml(xml).unwrap();
+        assert_eq!(score.notes[0].ornaments, vec!["trill-mark"]);
+        assert_eq!(score.notes[1].ornaments, vec!["inverted-mordent"]);
+        assert_eq!(score.notes[1].articulations, vec!["staccato"]);
+        assert!(score.notes[2].ornaments.is_empty());
+    }
+
+    #[test]
+    fn test_parse_beam_groups() {
+        let eighth = |step: &str, beam: &str| {
+            format!(
+                r#"<note><pitch><step>{}</step><octave>4</octave></pitch><duration>1</duration><type>eighth</type><beam number="1">{}</beam><beam number="2">{}</beam></note>"#,
+                step, beam, beam
+            )
+        };
+        let xml = format!(
+            r#"<score-partwise version="3.1">
+  <part id="P1">
+    <measure number="1">
+      <attributes><divisions>2</divisions></attributes>
+      {}{}{}{}{}{}
+      <note><pitch><step>C</step><octave>5</octave></pitch><duration>2</duration><type>quarter</type></note>
+    </measure>
+  </part>
+</score-partwise>"#,
+            eighth("C", "begin"),
+            eighth("D", "continue"),
+            eighth("E", "continue"),
+            eighth("F", "end"),
+            eighth("G", "begin"),
+            eighth("A", "end"),
+        );
+
+        let score = parse_musicxml(&xml).unwrap();
+        let groups: Vec<Option<u32>> = score.notes.iter().map(|n| n.beam_group).collect();
+        assert_eq!(
+            groups,
+            vec![Some(1), Some(1), Some(1), Some(1), Some(2), Some(2), None]
+        );
+    }
+
+    #[test]
+    fn test_parse_senza_misura() {
+        let xml = r#"<score-partwise version="3.1">
+  <part id="P1">
+    <measure number="1">
+      <attributes>
+        <divisions>1</divisions>
+        <time><beats>4</beats><beat-type>4</beat-type></time>
+      </attributes>
+      <note><pitch><step>C</step><octave>4</octave></pitch><duration>4</duration><type>whole</type></note>
+    </measure>
+    <measure number="2">
+      <attributes><time><senza-misura/></time></attributes>
+      <note><pitch><step>D</step><octave>4</octave></pitch><duration>1</duration><type>quarter</type></note>
+      <note><pitch><step>E</step><octave>4</octave></pitch><duration>1</duration><type>quarter</type></note>
+      <note><pitch><step>F</step><octave>4</octave></pitch><duration>1</duration><type>quarter</type></note>
+      <note><pitch><step>G</step><octave>4</octave></pitch><duration>1</duration><type>quarter</type></note>
+      <note><pitch><step>A</step><octave>4</octave></pitch><duration>1</duration><type>quarter</type></note>
+    </measure>
+    <measure number="3">
+      <attributes><time><beats>4</beats><beat-type>4</beat-type></time></attributes>
+      <note><pitch><step>C</step><octave>5</octave></pitch><duration>4</duration><type>whole</type></note>
+    </measure>
+  </part>
+</score-partwise>"#;
+
+        let score = parse_musicxml(xml).unwrap();
+        assert!(!score.measures[0].unmetered);
+        assert!(score.measures[1].unmetered);
+        assert_eq!(score.measures[1].duration_beats, 5.0);
+        assert!(!score.measures[2].unmetered);
+    }
+
+    #[test]
+    fn test_parse_unmetered_until_time() {
+        let xml = r#"<score-partwise version="3.1">
+  <part id="P1">
+    <measure number="1">
+      <attributes><divisions>1</divisions></attributes>
+      <note><pitch><step>C</step><octave>4</octave></pitch><duration>6</duration></note>
+    </measure>
+    <measure number="2">
+      <attributes><time><beats>3</beats><beat-type>4</beat-type></time></attributes>
+      <note><pitch><step>D</step><octave>4</octave></pitch><duration>3</duration></note>
+    </measure>
+  </part>
+</score-partwise>"#;
+
+        // No <time> yet: the free-length opening is not held to a default 4/4
+        let (score, warnings) = parse_musicxml_with_warnings(xml).unwrap();
+        assert!(score.measures[0].unmetered);
+        assert_eq!(score.measures[0].duration_beats, 6.0);
+        assert!(!score.measures[1].unmetered);
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_parse_dynamics() {
+        let xml = r#"<score-partwise version="3.1">
+  <part id="P1">
+    <measure number="1">
+      <attributes><divisions>1</divisions></attributes>
+      <note><pitch><step>C</step><octave>4</octave></pitch><duration>1</duration><type>quarter</type></note>
+      <direction><direction-type><dynamics><p/></dynamics></direction-type></direction>
+      <note><pitch><step>D</step><octave>4</octave></pitch><duration>1</duration><type>quarter</type></note>
+      <note><pitch><step>E</step><octave>4</octave></pitch><duration>1</duration><type>quarter</type></note>
+      <direction><direction-type><dynamics><ff/></dynamics></direction-type></direction>
+      <note><pitch><step>F</step><octave>4</octave></pitch><duration>1</duration><type>quarter</type></note>
+    </measure>
+  </part>
+</score-partwise>"#;
+
+        let score = parse_musicxml(xml).unwrap();
+        let dynamics: Vec<Option<&str>> =
+            score.notes.iter().map(|n| n.dynamics.as_deref()).collect();
+        assert_eq!(dynamics, vec![None, Some("p"), Some("p"), Some("ff")]);
+    }
+
+    #[test]
+    fn test_parse_direction_with_several_types() {
+        // One <direction> carrying a dynamic, a metronome mark, a text
+        // direction and its playback <sound>
+        let xml = r#"<score-partwise version="3.1">
+  <part id="P1">
+    <measure number="1">
+      <attributes><divisions>1</divisions></attributes>
+      <direction placement="below">
+        <direction-type><dynamics><mf/></dynamics></direction-type>
+        <direction-type><metronome><beat-unit>quarter</beat-unit><per-minute>84</per-minute></metronome></direction-type>
+        <direction-type><words>cantabile</words></direction-type>
+        <sound dynamics="80"/>
+      </direction>
+      <note><pitch><step>C</step><octave>4</octave></pitch><duration>1</duration><type>quarter</type></note>
+      <direction>
+        <direction-type><words>marcato</words></direction-type>
+        <direction-type><dynamics><sfz/></dynamics></direction-type>
+      </direction>
+      <note><pitch><step>D</step><octave>4</octave></pitch><duration>1</duration><type>quarter</type></note>
+      <note><pitch><step>E</step><octave>4</octave></pitch><duration>1</duration><type>quarter</type></note>
+    </measure>
+  </part>
+</score-partwise>"#;
+
+        let score = parse_musicxml(xml).unwrap();
+        let dynamics: Vec<Option<&str>> =
+            score.notes.iter().map(|n| n.dynamics.as_deref()).collect();
+        assert_eq!(dynamics, vec![Some("mf"), Some("sfz"), Some("mf")]);
+        assert_eq!(score.notes[0].velocity, Some(72));
+        assert_eq!(score.tempo, 84.0);
+    }
+
+    #[test]
+    fn test_parse_wedge_spans() {
+        // A crescendo across measures 1-2, then an unterminated diminuendo
+        let xml = r#"<score-partwise version="3.1">
+  <part id="P1">
+    <measure number="1">
+      <attributes><divisions>1</divisions></attributes>
+      <note><pitch><step>C</step><octave>4</octave></pitch><duration>1</duration><type>quarter</type></note>
+      <direction><direction-type><wedge type="crescendo" number="1"/></direction-type></direction>
+      <note><pitch><step>D</step><octave>4</octave></pitch><duration>3</duration><type>half</type><dot/></note>
+    </measure>
+    <measure number="2">
+      <note><pitch><step>E</step><octave>4</octave></pitch><duration>2</duration><type>half</type></note>
+      <direction><direction-type><wedge type="continue"/></direction-type></direction>
+      <note><pitch><step>F</step><octave>4</octave></pitch><duration>2</duration><type>half</type></note>
+      <direction>
+        <direction-type><wedge type="stop" number="1"/></direction-type>
+        <direction-type><dynamics><f/></dynamics></direction-type>
+      </direction>
+    </measure>
+    <measure number="3">
+      <direction><direction-type><wedge type="diminuendo"/></direction-type></direction>
+      <note><pitch><step>G</step><octave>4</octave></pitch><duration>4</duration><type>whole</type></note>
+    </measure>
+  </part>
+</score-partwise>"#;
+
+        let (score, warnings) = parse_musicxml_with_warnings(xml).unwrap();
+        let spans: Vec<(f64, f64, &str)> = score
+            .wedges
+            .iter()
+            .map(|w| (w.start_beat, w.end_beat, w.direction.as_str()))
+            .collect();
+        assert_eq!(spans, vec![(1.0, 8.0, "crescendo"), (8.0, 12.0, "diminuendo")]);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("no stop"));
+
+        let slice = score.slice_measures(2, 2);
+        assert_eq!(slice.wedges.len(), 1);
+        assert_eq!((slice.wedges[0].start_beat, slice.wedges[0].end_beat), (0.0, 4.0));
+    }
+
+    #[test]
+    fn test_parse_accidentals_for_display() {
+        // F#, then a natural cancelling it, then a courtesy sharp in the next bar
+        let xml = r#"<score-partwise version="3.1">
+  <part id="P1">
+    <measure number="1">
+      <attributes><divisions>1</divisions></attributes>
+      <note><pitch><step>F</step><alter>1</alter><octave>4</octave></pitch><duration>1</duration><type>quarter</type><accidental>sharp</accidental></note>
+      <note><pitch><step>F</step><octave>4</octave></pitch><duration>1</duration><type>quarter</type><accidental>natural</accidental></note>
+      <note><pitch><step>G</step><octave>4</octave></pitch><duration>2</duration><type>half</type></note>
+    </measure>
+    <measure number="2">
+      <note><pitch><step>F</step><alter>1</alter><octave>4</octave></pitch><duration>4</duration><type>whole</type><accidental cautionary="yes">sharp</accidental></note>
+    </measure>
+  </part>
+</score-partwise>"#;
+
+        let score = parse_musicxml(xml).unwrap();
+        let spelled: Vec<(i32, Option<&str>)> = score
+            .notes
+            .iter()
+            .map(|n| (n.midi, n.accidental.as_deref()))
+            .collect();
+        assert_eq!(
+            spelled,
+            vec![(66, Some("sharp")), (65, Some("natural")), (67, None), (66, Some("sharp"))]
+        );
+    }
+
+    #[test]
+    fn test_parse_glissando_and_slide() {
+        let xml = r#"<score-partwise version="3.1">
+  <part id="P1">
+    <measure number="1">
+      <attributes><divisions>1</divisions></attributes>
+      <note><pitch><step>C</step><octave>5</octave></pitch><duration>1</duration><type>quarter</type>
+        <notations><glissando line-type="wavy" number="1" type="start">gliss.</glissando></notations></note>
+      <note><pitch><step>G</step><octave>5</octave></pitch><duration>1</duration><type>quarter</type>
+        <notations><glissando number="1" type="stop"/><slide number="1" type="start"/></notations></note>
+      <note><pitch><step>E</step><octave>5</octave></pitch><duration>1</duration><type>quarter</type>
+        <notations><slide number="1" type="stop"/></notations></note>
+      <note><pitch><step>C</step><octave>5</octave></pitch><duration>1</duration><type>quarter</type></note>
+    </measure>
+  </part>
+</score-partwise>"#;
+
+        let score = parse_musicxml(xml).unwrap();
+        let glides: Vec<bool> = score.notes.iter().map(|n| n.glissando_to).collect();
+        assert_eq!(glides, vec![true, true, false, false]);
+    }
+
+    #[test]
+    fn test_parse_invalid_tempo_and_divisions() {
+        let xml = r#"<score-partwise version="3.1"><part id="P1"><measure number="1">
+            <attributes><divisions>0</divisions></attributes>
+            <direction><direction-type><metronome><beat-unit>quarter</beat-unit><per-minute>-5</per-minute></metronome></direction-type></direction>
+            <note><pitch><step>C</step><octave>5</octave></pitch><duration>2</duration><type>half</type></note>
+            <direction><sound tempo="0"/></direction>
+            <note><pitch><step>D</step><octave>5</octave></pitch><duration>2</duration><type>half</type></note>
+        </measure></part></score-partwise>"#;
+
+        let (score, warnings) = parse_musicxml_with_warnings(xml).unwrap();
+        assert_eq!(score.tempo, 120.0);
+        // Divisions stay at the default of 1 per beat
+        assert_eq!(score.notes[1].start_beat, 2.0);
+        assert_eq!(warnings.len(), 3);
+        assert!(warnings[0].contains("invalid divisions"));
+        assert!(warnings[1].contains("invalid tempo -5"));
+        assert!(warnings[2].contains("invalid tempo 0"));
+    }
+
+    #[test]
+    fn test_parse_selected_part_uses_own_attributes() {
+        let xml = r#"<score-partwise version="3.1">
+  <part-list>
+    <score-part id="P1"><part-name>Horn in F</part-name></score-part>
+    <score-part id="P2"><part-name>Trumpet in Bb</part-name></score-part>
+  </part-list>
+  <part id="P1">
+    <measure number="1">
+      <attributes><divisions>4</divisions><key><fifths>-2</fifths></key>
+        <time><beats>3</beats><beat-type>4</beat-type></time></attributes>
+      <note><pitch><step>F</step><octave>4</octave></pitch><duration>12</duration><type>half</type><dot/></note>
+    </measure>
+  </part>
+  <part id="P2">
+    <measure number="1">
+      <note><pitch><step>C</step><octave>5</octave></pitch><duration>1</duration><type>quarter</type></note>
+      <note><pitch><step>D</step><octave>5</octave></pitch><duration>3</duration><type>half</type><dot/></note>
+    </measure>
+  </part>
+</score-partwise>"#;
+
+        // Part 2 declares no attributes: the defaults apply, not part 1's
+        let score = parse_musicxml_part(xml, "P2").unwrap();
+        let beats: Vec<(f64, f64)> =
+            score.notes.iter().map(|n| (n.start_beat, n.duration_beats)).collect();
+        assert_eq!(beats, vec![(0.0, 1.0), (1.0, 3.0)]);
+        assert_eq!(score.key_fifths, 0);
+        assert_eq!(score.measures.len(), 1);
+        assert_eq!((score.measures[0].time_sig_num, score.measures[0].duration_beats), (4, 4.0));
+        assert_eq!(score.transpose.map(|t| t.chromatic), Some(-2));
+
+        let score = parse_musicxml_part(xml, "P1").unwrap();
+        assert_eq!(score.notes.len(), 1);
+        assert_eq!(score.notes[0].duration_beats, 3.0);
+        assert_eq!(score.key_fifths, -2);
+        assert_eq!(score.transpose.map(|t| t.chromatic), Some(-7));
+
+        assert!(parse_musicxml_part(xml, "P3").is_err());
+
+        // Reading every part, part 1's last measure keeps its 3/4
+        let score = parse_musicxml(xml).unwrap();
+        assert_eq!(score.measures[0].time_sig_num, 3);
+        assert_eq!(score.measures[1].time_sig_num, 4);
+    }
+
+    #[test]
+    fn test_parse_triplets_exact_beats() {
+        // 100 measures of 12 triplet eighths: 1/3 beat each at divisions=3
+        let triplet = "<note><pitch><step>C</step><octave>5</octave></pitch>\
+            <duration>1</duration><type>eighth</type></note>";
+        let mut xml = String::from(r#"<score-partwise version="3.1"><part id="P1">"#);
+        for m in 1..=100 {
+            xml.push_str(&format!(r#"<measure number="{}">"#, m));
+            if m == 1 {
+                xml.push_str("<attributes><divisions>3</divisions></attributes>");
+            }
+            for _ in 0..12 {
+                xml.push_str(triplet);
+            }
+            xml.push_str("</measure>");
+        }
+        xml.push_str("</part></score-partwise>");
+
+        let score = parse_musicxml(&xml).unwrap();
+        assert_eq!(score.notes.len(), 1200);
+        assert_eq!(score.total_beats, 400.0);
+        assert_eq!(score.measures[99].start_beat, 396.0);
+        assert_eq!(score.notes[1199].start_beat, 1199.0 / 3.0);
+    }
+
+    #[test]
+    fn test_parse_mismatched_decimal_divisions() {
+        // Each measure has one note of duration 1 at a different fine
+        // decimal division; exact sums over these denominators overflow i64
+        let divisions = ["0.123456789", "0.987654321", "0.111111113", "0.777777779"];
+        let mut xml = String::from(r#"<score-partwise version="3.1"><part id="P1">"#);
+        for (i, d) in divisions.iter().enumerate() {
+            xml.push_str(&format!(
+                r#"<measure number="{}"><attributes><divisions>{}</divisions>
+                <time><beats>12</beats><beat-type>4</beat-type></time></attributes>
+                <note><pitch><step>C</step><octave>5</octave></pitch><duration>1</duration></note>
+                </measure>"#,
+                i + 1,
+                d
+            ));
+        }
+        xml.push_str("</part></score-partwise>");
+
+        let score = parse_musicxml(&xml).unwrap();
+        let lengths: Vec<f64> = divisions.iter().map(|d| 1.0 / d.parse::<f64>().unwrap()).collect();
+        let mut start = 0.0;
+        for (note, length) in score.notes.iter().zip(&lengths) {
+            assert!((note.start_beat - start).abs() < 1e-6, "{} vs {}", note.start_beat, start);
+            assert!((note.duration_beats - length).abs() < 1e-6);
+            start += length;
+        }
+        assert!((score.total_beats - start).abs() < 1e-6, "{}", score.total_beats);
+        assert!((score.total_beats - 19.398).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_parse_overlong_measure_warns() {
+        // Measure 1 holds five beats of 4/4; measure 2 is full but not over
+        let xml = r#"<score-partwise version="3.1"><part id="P1">
+            <measure number="1">
+                <attributes><divisions>1</divisions><time><beats>4</beats><beat-type>4</beat-type></time></attributes>
+                <note><pitch><step>C</step><octave>5</octave></pitch><duration>3</duration><type>half</type><dot/></note>
+                <note><pitch><step>D</step><octave>5</octave></pitch><duration>2</duration><type>half</type></note>
+            </measure>
+            <measure number="2">
+                <note><pitch><step>E</step><octave>5</octave></pitch><duration>4</duration><type>whole</type></note>
+            </measure>
+        </part></score-partwise>"#;
+
+        let (score, warnings) = parse_musicxml_with_warnings(xml).unwrap();
+        // The overlong measure keeps its real span, so the next one follows on
+        assert_eq!(score.measures[0].duration_beats, 5.0);
+        assert_eq!(score.measures[1].start_beat, 5.0);
+        assert_eq!(score.measure_at(4.5).map(|m| m.number), Some(1));
+        assert_eq!(score.measures[1].duration_beats, 4.0);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("Measure 1: contents last 5 beats"));
+    }
+
+    #[test]
+    fn test_parse_zero_duration_uses_type() {
+        let xml = r#"<score-partwise version="3.1"><part id="P1"><measure number="1">
+            <attributes><divisions>2</divisions></attributes>
+            <note><grace/><pitch><step>B</step><octave>4</octave></pitch><type>eighth</type></note>
+            <note><pitch><step>C</step><octave>5</octave></pitch><duration>0</duration><type>quarter</type></note>
+            <note><pitch><step>D</step><octave>5</octave></pitch><duration>3</duration><type>quarter</type><dot/></note>
+        </measure></part></score-partwise>"#;
+
+        let (score, warnings) = parse_musicxml_with_warnings(xml).unwrap();
+        // The grace note stays zero-length without a warning
+        assert_eq!(score.notes[0].duration_beats, 0.0);
+        assert_eq!(score.notes[1].duration_beats, 1.0);
+        assert_eq!(score.notes[2].start_beat, 1.0);
+        assert_eq!(score.total_beats, 2.5);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("Measure 1") && warnings[0].contains("zero duration"));
+
+        assert_eq!(note_type_beats("quarter", 1), Some(Beats::new(3, 2)));
+        assert_eq!(note_type_beats("half", 2), Some(Beats::new(7, 2)));
+        assert_eq!(note_type_beats("bogus", 0), None);
+    }
+
+    #[test]
+    fn test_beats_to_note_type() {
+        assert_eq!(beats_to_note_type(4.0), "whole");
+        assert_eq!(beats_to_note_type(1.5), "quarter");
+        assert_eq!(beats_to_note_type(0.5), "eighth");
+        assert_eq!(beats_to_note_type(0.25), "16th");
+        assert_eq!(beats_to_note_type(0.0), "quarter");
+    }
+
+    #[test]
+    fn test_beats_parse_decimal() {
+        assert_eq!(Beats::parse("1.5"), Some(Beats::new(3, 2)));
+        assert_eq!(Beats::parse("4"), Some(Beats::new(4, 1)));
+        assert_eq!(Beats::parse("abc"), None);
+        assert_eq!(Beats::parse("999999999999999999.5"), None);
+        assert_eq!((Beats::new(1, 3) + Beats::new(2, 3)).to_f64(), 1.0);
+        assert!(Beats::new(1, 3) < Beats::new(1, 2));
+    }
+
+    #[test]
+    fn test_parse_oversized_duration() {
+        let xml = r#"<score-partwise version="3.1"><part id="P1"><measure number="1">
+            <attributes><divisions>1</divisions></attributes>
+            <note><pitch><step>C</step><octave>4</octave></pitch><duration>999999999999999999.5</duration></note>
+            <note><pitch><step>D</step><octave>4</octave></pitch><duration>1</duration></note>
+            </measure></part></score-partwise>"#;
+
+        // Rejected as a bad value rather than overflowing
+        let score = parse_musicxml(xml).unwrap();
+        assert_eq!(score.notes[0].duration_beats, 0.0);
+        assert_eq!(score.notes[1].start_beat, 0.0);
+    }
+
+    #[test]
+    fn test_parse_sforzando_and_other_dynamics() {
+        let xml = r#"<score-partwise version="3.1">
+  <part id="P1">
+    <measure number="1">
+      <attributes><divisions>1</divisions></attributes>
+      <direction><direction-type><dynamics><mp/></dynamics></direction-type></direction>
+      <note><pitch><step>C</step><octave>4</octave></pitch><duration>1</duration><type>quarter</type></note>
+      <direction><direction-type><dynamics><sfz/></dynamics></direction-type></direction>
+      <note><pitch><step>D</step><octave>4</octave></pitch><duration>1</duration><type>quarter</type></note>
+      <note><pitch><step>E</step><octave>4</octave></pitch><duration>1</duration><type>quarter</type></note>
+      <direction><direction-type><dynamics><other-dynamics>pppp-ish</other-dynamics></dynamics></direction-type></direction>
+      <note><pitch><step>F</step><octave>4</octave></pitch><duration>1</duration><type>quarter</type></note>
+    </measure>
+  </part>
+</score-partwise>"#;
+
+        let score = parse_musicxml(xml).unwrap();
+        let dynamics: Vec<Option<&str>> =
+            score.notes.iter().map(|n| n.dynamics.as_deref()).collect();
+        // sfz marks only the note that follows it
+        assert_eq!(
+            dynamics,
+            vec![Some("mp"), Some("sfz"), Some("mp"), Some("pppp-ish")]
+        );
+    }
+
     #[test]
     fn test_parse_happy_birthday() {
         let xml = include_str!("../../web/assets/happy_birthday.musicxml");
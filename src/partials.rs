@@ -0,0 +1,155 @@
+use alloc::vec::Vec;
+
+#[cfg(not(feature = "std"))]
+use crate::math::FloatMath;
+
+/// Written pedal fundamental of the open (no valve) Bb trumpet: C3.
+pub const OPEN_FUNDAMENTAL_MIDI: i32 = 48;
+
+/// Semitones lowered by valves 1, 2 and 3.
+const VALVE_SEMITONES: [i32; 3] = [2, 1, 3];
+
+/// Highest partial considered when placing a pitch on a fingering's series.
+const MAX_PARTIAL: u32 = 16;
+
+/// A pitch further than this from every partial is not on the series.
+const PARTIAL_TOLERANCE_SEMITONES: f64 = 0.5;
+
+/// Valve combinations from the fewest semitones lowered to the most, the
+/// order a player prefers them in when several reach the same note.
+const FINGERINGS_BY_LENGTH: [[bool; 3]; 7] = [
+    [false, false, false],
+    [false, true, false],
+    [true, false, false],
+    [true, true, false],
+    [false, true, true],
+    [true, false, true],
+    [true, true, true],
+];
+
+/// Partials too far out of tune to be a note's standard fingering (the 7th
+/// is about a third of a semitone flat).
+const OUT_OF_TUNE_PARTIALS: [u32; 4] = [7, 11, 13, 14];
+
+/// A standard fingering's partial lies within this of the written pitch.
+const FINGERING_TOLERANCE_SEMITONES: f64 = 0.2;
+
+/// Written MIDI pitch of a fingering's fundamental; `fingering` lists
+/// whether valves 1, 2 and 3 are pressed.
+pub fn fingering_fundamental(fingering: [bool; 3]) -> i32 {
+    let lowered: i32 = VALVE_SEMITONES
+        .iter()
+        .zip(fingering)
+        .filter(|&(_, pressed)| pressed)
+        .map(|(semitones, _)| semitones)
+        .sum();
+    OPEN_FUNDAMENTAL_MIDI - lowered
+}
+
+/// MIDI pitches of the first `count` partials of the series on
+/// `fundamental_midi`, starting with the fundamental itself, each rounded
+/// to the nearest semitone (so the flat 7th partial lands on the flat 7th).
+pub fn harmonic_series(fundamental_midi: i32, count: usize) -> Vec<i32> {
+    (1..=count)
+        .map(|n| fundamental_midi + (12.0 * (n as f64).log2()).round() as i32)
+        .collect()
+}
+
+/// Partial number (1 = fundamental) nearest to `midi` on the series above
+/// `fundamental`, if within `PARTIAL_TOLERANCE_SEMITONES` of it.
+fn nearest_partial(midi: f64, fundamental: i32) -> Option<u32> {
+    let above = midi - fundamental as f64;
+    (1..=MAX_PARTIAL)
+        .map(|n| (n, (above - 12.0 * (n as f64).log2()).abs()))
+        .filter(|&(_, distance)| distance <= PARTIAL_TOLERANCE_SEMITONES)
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(core::cmp::Ordering::Equal))
+        .map(|(n, _)| n)
+}
+
+/// Standard fingering for a written pitch, for a fingering chart: the first
+/// combination in `FINGERINGS_BY_LENGTH` with an in-tune partial (above the
+/// pedal) on it. `None` below F#3 or for pitches no fingering reaches.
+pub fn fingering_for_midi(midi: i32) -> Option<[bool; 3]> {
+    FINGERINGS_BY_LENGTH.into_iter().find(|&fingering| {
+        let fundamental = fingering_fundamental(fingering);
+        match nearest_partial(midi as f64, fundamental) {
+            Some(n) if n >= 2 && !OUT_OF_TUNE_PARTIALS.contains(&n) => {
+                let above = (midi - fundamental) as f64;
+                (above - 12.0 * (n as f64).log2()).abs() <= FINGERING_TOLERANCE_SEMITONES
+            }
+            _ => false,
+        }
+    })
+}
+
+/// Diagnose a cracked note: how many partials the played pitch landed away
+/// from the intended one on the given fingering (+1 = one partial too high,
+/// 0 = right partial). `None` if the target or the played pitch does not sit
+/// on that fingering's harmonic series.
+pub fn partial_error(played_midi: f64, target_midi: i32, fingering: [bool; 3]) -> Option<i32> {
+    let fundamental = fingering_fundamental(fingering);
+    let intended = nearest_partial(target_midi as f64, fundamental)?;
+    let landed = nearest_partial(played_midi, fundamental)?;
+    Some(landed as i32 - intended as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OPEN: [bool; 3] = [false, false, false];
+
+    #[test]
+    fn test_partial_error_overshoot() {
+        // Aiming for G4 (3rd partial, open) but cracking up to C5 (4th)
+        assert_eq!(partial_error(72.1, 67, OPEN), Some(1));
+        assert_eq!(partial_error(67.0, 67, OPEN), Some(0));
+        // Slotting down to C4 (2nd partial)
+        assert_eq!(partial_error(60.0, 67, OPEN), Some(-1));
+    }
+
+    #[test]
+    fn test_harmonic_series_open_bugle_calls() {
+        // Pedal C3, then the bugle-call notes C4 G4 C5 E5 G5, the flat
+        // Bb5 and C6
+        let series = harmonic_series(fingering_fundamental(OPEN), 8);
+        assert_eq!(series, vec![48, 60, 67, 72, 76, 79, 82, 84]);
+        // Valve 2 shifts the whole series down a semitone
+        let second = harmonic_series(fingering_fundamental([false, true, false]), 4);
+        assert_eq!(second, vec![47, 59, 66, 71]);
+        assert!(harmonic_series(48, 0).is_empty());
+    }
+
+    #[test]
+    fn test_fingering_for_midi_chart() {
+        // C4 major scale: C D E F G A B C
+        let expected = [
+            (60, OPEN),
+            (62, [true, false, true]),
+            (64, [true, true, false]),
+            (65, [true, false, false]),
+            (67, OPEN),
+            (69, [true, true, false]),
+            (71, [false, true, false]),
+            (72, OPEN),
+        ];
+        for (midi, fingering) in expected {
+            assert_eq!(fingering_for_midi(midi), Some(fingering), "midi {}", midi);
+        }
+        // Above the staff the flat 7th partial is skipped: Bb5 is 1, not open
+        assert_eq!(fingering_for_midi(82), Some([true, false, false]));
+        assert_eq!(fingering_for_midi(80), Some([false, true, true]));
+        // The pedal C and anything below F#3 have no standard fingering
+        assert_eq!(fingering_for_midi(48), None);
+        assert_eq!(fingering_for_midi(53), None);
+    }
+
+    #[test]
+    fn test_partial_error_fingerings() {
+        assert_eq!(fingering_fundamental([true, true, true]), 42);
+        // On 1-2-3 (F#2 fundamental) C#4 is the 3rd partial and F#4 the 4th
+        assert_eq!(partial_error(66.0, 61, [true, true, true]), Some(1));
+        // C#4 is not on the open series
+        assert_eq!(partial_error(61.0, 61, OPEN), None);
+    }
+}
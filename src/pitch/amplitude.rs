@@ -0,0 +1,58 @@
+use alloc::vec::Vec;
+
+#[cfg(not(feature = "std"))]
+use crate::math::FloatMath;
+use crate::scoring::types::DynamicsTrailPoint;
+
+/// RMS amplitude of each `hop`-sample frame, stamped with the frame's start
+/// in beats at `tempo`. A trailing partial frame is included. Returns an
+/// empty trail for a zero hop or non-positive sample rate.
+pub fn amplitude_trail(
+    samples: &[f32],
+    sample_rate: f32,
+    hop: usize,
+    tempo: f64,
+) -> Vec<DynamicsTrailPoint> {
+    if hop == 0 || sample_rate <= 0.0 {
+        return Vec::new();
+    }
+    let beats_per_sample = tempo / 60.0 / sample_rate as f64;
+    samples
+        .chunks(hop)
+        .enumerate()
+        .map(|(i, frame)| {
+            let energy: f64 = frame.iter().map(|&x| (x as f64) * (x as f64)).sum();
+            DynamicsTrailPoint {
+                beat: (i * hop) as f64 * beats_per_sample,
+                rms: (energy / frame.len() as f64).sqrt(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::f32::consts::PI;
+
+    #[test]
+    fn test_amplitude_trail_decrescendo() {
+        let sample_rate = 8000.0;
+        // One second loud, one second soft, at 120 bpm
+        let samples: Vec<f32> = (0..16000)
+            .map(|i| {
+                let amp = if i < 8000 { 0.8 } else { 0.1 };
+                amp * (2.0 * PI * 440.0 * i as f32 / sample_rate).sin()
+            })
+            .collect();
+
+        let trail = amplitude_trail(&samples, sample_rate, 800, 120.0);
+        assert_eq!(trail.len(), 20);
+        assert_eq!(trail[10].beat, 2.0);
+        // Sine RMS is amplitude / sqrt(2)
+        assert!((trail[0].rms - 0.8 / 2f64.sqrt()).abs() < 0.01);
+        assert!((trail[19].rms - 0.1 / 2f64.sqrt()).abs() < 0.01);
+        assert!(trail.windows(2).all(|w| w[1].rms <= w[0].rms + 0.01));
+        assert!(amplitude_trail(&samples, sample_rate, 0, 120.0).is_empty());
+    }
+}
@@ -0,0 +1,101 @@
+use alloc::vec::Vec;
+
+#[cfg(not(feature = "std"))]
+use crate::math::FloatMath;
+use crate::scoring::types::PlayedNote;
+
+/// Analysis frame length in seconds.
+const FRAME_SECONDS: f32 = 0.01;
+/// Frames quieter than this RMS are silence, not breath.
+const BREATH_MIN_RMS: f32 = 0.01;
+/// Zero crossings per sample above which a frame is noise rather than tone.
+/// White noise crosses about every other sample; a trumpet tone, even a
+/// bright high one, far less often.
+const BREATH_MIN_CROSSING_RATE: f32 = 0.3;
+
+/// Whether a frame sounds like breath noise: audible, with the high zero
+/// crossing rate of unpitched, high-frequency energy.
+fn is_breath_frame(frame: &[f32]) -> bool {
+    let energy: f32 = frame.iter().map(|x| x * x).sum();
+    let rms = (energy / frame.len() as f32).sqrt();
+    let crossings = frame
+        .windows(2)
+        .filter(|w| (w[0] >= 0.0) != (w[1] >= 0.0))
+        .count();
+    rms >= BREATH_MIN_RMS && crossings as f32 / frame.len() as f32 >= BREATH_MIN_CROSSING_RATE
+}
+
+/// Beat positions (at `tempo`) where audible breaths start between
+/// consecutive played notes, e.g. to tell a student they breathe too late.
+/// A run of noisy frames counts as one breath, placed at its first frame.
+/// `samples` start at beat 0; `played` onsets need not be sorted.
+pub fn inter_note_breaths(
+    samples: &[f32],
+    sample_rate: f32,
+    tempo: f64,
+    played: &[PlayedNote],
+) -> Vec<f64> {
+    let frame_len = (sample_rate * FRAME_SECONDS) as usize;
+    if frame_len < 2 || tempo <= 0.0 {
+        return Vec::new();
+    }
+    let samples_per_beat = 60.0 / tempo * sample_rate as f64;
+    let mut onsets: Vec<f64> = played.iter().map(|p| p.onset_beat).collect();
+    onsets.sort_by(|a, b| a.partial_cmp(b).unwrap_or(core::cmp::Ordering::Equal));
+
+    let mut breaths = Vec::new();
+    for gap in onsets.windows(2) {
+        let start = ((gap[0] * samples_per_beat).max(0.0) as usize).min(samples.len());
+        let end = ((gap[1] * samples_per_beat).max(0.0) as usize).min(samples.len());
+        let mut in_breath = false;
+        for (i, frame) in samples[start..end].chunks_exact(frame_len).enumerate() {
+            let breath = is_breath_frame(frame);
+            if breath && !in_breath {
+                breaths.push((start + i * frame_len) as f64 / samples_per_beat);
+            }
+            in_breath = breath;
+        }
+    }
+    breaths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::f32::consts::PI;
+
+    #[test]
+    fn test_breath_between_tones() {
+        let sample_rate = 8000.0;
+        // At 120 bpm: a tone for beat 0-1, silence, a breath at beat 1.2-1.4,
+        // silence, then a tone from beat 2
+        let mut seed: u32 = 12345;
+        let samples: Vec<f32> = (0..12000)
+            .map(|i| {
+                let t = i as f32 / sample_rate;
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                let noise = (seed >> 16) as f32 / 32768.0 - 1.0;
+                if (0.6..0.7).contains(&t) {
+                    0.05 * noise
+                } else if (0.5..1.0).contains(&t) {
+                    0.0
+                } else {
+                    0.5 * (2.0 * PI * 440.0 * t).sin()
+                }
+            })
+            .collect();
+        let note = |onset_beat: f64| PlayedNote {
+            onset_beat,
+            midi_float: 69.0,
+            midi_rounded: 69,
+            confidence: 0.9,
+        };
+
+        let breaths = inter_note_breaths(&samples, sample_rate, 120.0, &[note(2.0), note(0.0)]);
+        assert_eq!(breaths.len(), 1);
+        assert!((breaths[0] - 1.2).abs() < 0.03, "{:?}", breaths);
+
+        // Without a following note the gap is never closed
+        assert!(inter_note_breaths(&samples, sample_rate, 120.0, &[note(0.0)]).is_empty());
+    }
+}
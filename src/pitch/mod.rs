@@ -1 +1,4 @@
+pub mod amplitude;
+pub mod breath;
+pub mod spectrum;
 pub mod yin;
@@ -0,0 +1,160 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::f32::consts::PI;
+
+#[cfg(not(feature = "std"))]
+use crate::math::FloatMath;
+
+/// Number of harmonics reported by `spectral_profile`.
+pub const PROFILE_HARMONICS: usize = 8;
+
+/// Magnitude of a single frequency component using the Goertzel algorithm.
+/// Samples are Hann-windowed to limit leakage from neighbouring harmonics.
+fn goertzel_magnitude(samples: &[f32], sample_rate: f32, freq: f32) -> f32 {
+    let n = samples.len();
+    let omega = 2.0 * PI * freq / sample_rate;
+    let coeff = 2.0 * omega.cos();
+
+    let mut s1 = 0.0f32;
+    let mut s2 = 0.0f32;
+    for (i, &x) in samples.iter().enumerate() {
+        let window = 0.5 - 0.5 * (2.0 * PI * i as f32 / (n - 1) as f32).cos();
+        let s0 = x * window + coeff * s1 - s2;
+        s2 = s1;
+        s1 = s0;
+    }
+
+    let power = s1 * s1 + s2 * s2 - coeff * s1 * s2;
+    power.max(0.0).sqrt()
+}
+
+/// Relative amplitudes of the first `PROFILE_HARMONICS` harmonics of
+/// `fundamental_hz`, normalized so the strongest harmonic is 1.0.
+/// Harmonics above Nyquist report 0.0. Returns an empty vector for
+/// unusable input (too few samples or a non-positive frequency).
+pub fn spectral_profile(samples: &[f32], sample_rate: f32, fundamental_hz: f32) -> Vec<f32> {
+    if samples.len() < 2 || sample_rate <= 0.0 || fundamental_hz <= 0.0 {
+        return Vec::new();
+    }
+
+    let nyquist = sample_rate / 2.0;
+    let amplitudes: Vec<f32> = (1..=PROFILE_HARMONICS)
+        .map(|h| {
+            let freq = fundamental_hz * h as f32;
+            if freq >= nyquist {
+                0.0
+            } else {
+                goertzel_magnitude(samples, sample_rate, freq)
+            }
+        })
+        .collect();
+
+    let max = amplitudes.iter().cloned().fold(0.0f32, f32::max);
+    if max <= 0.0 {
+        return vec![0.0; PROFILE_HARMONICS];
+    }
+    amplitudes.iter().map(|a| a / max).collect()
+}
+
+/// Settings for `hps_pitch`.
+#[derive(Clone, Debug)]
+pub struct HpsConfig {
+    pub min_hz: f32,
+    pub max_hz: f32,
+    /// Harmonics multiplied per candidate. More harmonics suppress octave
+    /// errors on low notes, but only fundamentals up to nyquist / harmonics
+    /// can be evaluated, so high notes fall to a subharmonic. 3 suits
+    /// trumpet at 44.1 kHz; use 2 at low sample rates or for the top register.
+    pub harmonics: usize,
+}
+
+impl Default for HpsConfig {
+    fn default() -> Self {
+        HpsConfig {
+            min_hz: 80.0,
+            max_hz: 1200.0,
+            harmonics: 3,
+        }
+    }
+}
+
+/// Candidate spacing for `hps_pitch`: 10 cents (2^(1/120)).
+const HPS_STEP_RATIO: f32 = 1.005_792_9;
+
+/// Fundamental estimate from a harmonic product spectrum: the candidate in
+/// `[min_hz, max_hz]` (on a 10-cent grid) whose first `harmonics` partials
+/// have the largest magnitude product. Candidates whose top harmonic would
+/// reach Nyquist are not evaluated. Returns 0.0 when nothing can be.
+pub fn hps_pitch(samples: &[f32], sample_rate: f32, config: &HpsConfig) -> f32 {
+    if samples.len() < 2 || sample_rate <= 0.0 || config.harmonics == 0 || config.min_hz <= 0.0 {
+        return 0.0;
+    }
+
+    let max_hz = config.max_hz.min(sample_rate / 2.0 / config.harmonics as f32);
+    let mut best = (0.0f32, 0.0f32);
+    let mut freq = config.min_hz;
+    while freq < max_hz {
+        let product: f32 = (1..=config.harmonics)
+            .map(|h| goertzel_magnitude(samples, sample_rate, freq * h as f32))
+            .product();
+        if product > best.1 {
+            best = (freq, product);
+        }
+        freq *= HPS_STEP_RATIO;
+    }
+    best.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spectral_profile_known_ratios() {
+        let sample_rate = 44100.0;
+        let fundamental = 220.0;
+        let samples: Vec<f32> = (0..4410)
+            .map(|i| {
+                let t = i as f32 / sample_rate;
+                0.4 * (2.0 * PI * fundamental * t).sin()
+                    + 0.2 * (2.0 * PI * 2.0 * fundamental * t).sin()
+                    + 0.1 * (2.0 * PI * 3.0 * fundamental * t).sin()
+            })
+            .collect();
+
+        let profile = spectral_profile(&samples, sample_rate, fundamental);
+        assert_eq!(profile.len(), PROFILE_HARMONICS);
+        assert!((profile[0] - 1.0).abs() < 0.05, "h1 = {}", profile[0]);
+        assert!((profile[1] - 0.5).abs() < 0.05, "h2 = {}", profile[1]);
+        assert!((profile[2] - 0.25).abs() < 0.05, "h3 = {}", profile[2]);
+        for (h, &amp) in profile.iter().enumerate().skip(3) {
+            assert!(amp < 0.05, "h{} should be near zero, got {}", h + 1, amp);
+        }
+    }
+
+    #[test]
+    fn test_hps_harmonic_count_high_note() {
+        // A 1000 Hz tone with its octave, sampled at 6 kHz
+        let sample_rate = 6000.0;
+        let samples: Vec<f32> = (0..2048)
+            .map(|i| {
+                let t = i as f32 / sample_rate;
+                0.5 * (2.0 * PI * 1000.0 * t).sin() + 0.25 * (2.0 * PI * 2000.0 * t).sin()
+            })
+            .collect();
+
+        let two = HpsConfig { harmonics: 2, ..Default::default() };
+        let four = HpsConfig { harmonics: 4, ..Default::default() };
+        let hz = hps_pitch(&samples, sample_rate, &two);
+        assert!((hz - 1000.0).abs() < 10.0, "2 harmonics: {}", hz);
+        // Four harmonics cap candidates at 750 Hz: the tone is misread
+        let hz = hps_pitch(&samples, sample_rate, &four);
+        assert!((hz - 1000.0).abs() > 100.0, "4 harmonics: {}", hz);
+    }
+
+    #[test]
+    fn test_spectral_profile_invalid_input() {
+        assert!(spectral_profile(&[], 44100.0, 440.0).is_empty());
+        assert!(spectral_profile(&[0.1, 0.2, 0.3], 44100.0, 0.0).is_empty());
+    }
+}
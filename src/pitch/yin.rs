@@ -1,5 +1,10 @@
+use alloc::vec;
+use alloc::vec::Vec;
 use serde::Serialize;
 
+#[cfg(not(feature = "std"))]
+use crate::math::FloatMath;
+
 #[derive(Serialize, Clone, Debug)]
 pub struct PitchResult {
     pub hz: f32,
@@ -18,6 +23,39 @@ impl PitchResult {
 }
 
 const YIN_THRESHOLD: f32 = 0.15;
+/// Relaxed threshold retried when no valley dips below `YIN_THRESHOLD`, so
+/// breathy notes still resolve to their first (fundamental) valley.
+pub const YIN_RETRY_THRESHOLD: f32 = 0.25;
+/// Global CMND minimum above which a frame is treated as unpitched.
+const YIN_MAX_MINIMUM: f32 = 0.5;
+
+/// Lag of the first CMND valley below `threshold` in `min_lag..=max_lag`,
+/// walked forward to the valley's local minimum.
+fn first_dip(cmnd: &[f32], min_lag: usize, max_lag: usize, threshold: f32) -> Option<usize> {
+    let tau = (min_lag..=max_lag).find(|&tau| cmnd[tau] < threshold)?;
+    let mut t = tau;
+    while t < max_lag && cmnd[t + 1] < cmnd[t] {
+        t += 1;
+    }
+    Some(t)
+}
+
+/// Best lag for a CMND curve: the first valley below `YIN_THRESHOLD`, else
+/// the first below `YIN_RETRY_THRESHOLD`, else the global minimum if it is
+/// at most `YIN_MAX_MINIMUM`. `None` means unpitched.
+fn pick_tau(cmnd: &[f32], min_lag: usize, max_lag: usize) -> Option<usize> {
+    first_dip(cmnd, min_lag, max_lag, YIN_THRESHOLD)
+        .or_else(|| first_dip(cmnd, min_lag, max_lag, YIN_RETRY_THRESHOLD))
+        .or_else(|| {
+            let mut best_tau = min_lag;
+            for tau in min_lag..=max_lag {
+                if cmnd[tau] < cmnd[best_tau] {
+                    best_tau = tau;
+                }
+            }
+            (cmnd[best_tau] <= YIN_MAX_MINIMUM).then_some(best_tau)
+        })
+}
 
 /// Pre-allocated YIN pitch detector. Reuses buffers across calls to avoid
 /// heap allocation on the hot path.
@@ -93,31 +131,10 @@ impl PitchDetector {
             }
         }
 
-        // Absolute threshold
-        let mut best_tau = 0usize;
-        for tau in min_lag..=max_lag {
-            if self.cmnd[tau] < YIN_THRESHOLD {
-                let mut t = tau;
-                while t + 1 <= max_lag && self.cmnd[t + 1] < self.cmnd[t] {
-                    t += 1;
-                }
-                best_tau = t;
-                break;
-            }
-        }
-
-        if best_tau == 0 {
-            let mut min_val = f32::MAX;
-            for tau in min_lag..=max_lag {
-                if self.cmnd[tau] < min_val {
-                    min_val = self.cmnd[tau];
-                    best_tau = tau;
-                }
-            }
-            if min_val > 0.5 {
-                return PitchResult::silence();
-            }
-        }
+        // Absolute threshold, with a relaxed retry
+        let Some(best_tau) = pick_tau(&self.cmnd, min_lag, max_lag) else {
+            return PitchResult::silence();
+        };
 
         // Parabolic interpolation
         let tau_refined = if best_tau > 0 && best_tau < max_lag {
@@ -150,6 +167,27 @@ impl PitchDetector {
     }
 }
 
+/// Periods of the expected pitch a window should span: YIN compares the
+/// first half of the window against lags up to half its length.
+const WINDOW_PERIODS: f32 = 4.0;
+/// Smallest window `recommended_window` suggests.
+const MIN_WINDOW: usize = 256;
+/// Lowest pitch assumed when the expected pitch is unknown (trumpet pedal range).
+const FALLBACK_HZ: f32 = 80.0;
+
+/// Buffer size for detecting pitches around `approx_hz`: at least
+/// `WINDOW_PERIODS` periods, rounded up to a power of two. Low notes get long
+/// windows for accuracy, high notes short ones for latency. A non-positive
+/// `approx_hz` is treated as 80 Hz.
+pub fn recommended_window(approx_hz: f32, sample_rate: f32) -> usize {
+    if sample_rate <= 0.0 {
+        return MIN_WINDOW;
+    }
+    let hz = if approx_hz > 0.0 { approx_hz } else { FALLBACK_HZ };
+    let samples = (WINDOW_PERIODS * sample_rate / hz).ceil() as usize;
+    samples.next_power_of_two().max(MIN_WINDOW)
+}
+
 /// Detect pitch using the YIN algorithm.
 /// Returns a PitchResult with frequency, confidence, and fractional MIDI number.
 pub fn detect_pitch_yin(samples: &[f32], sample_rate: f32) -> PitchResult {
@@ -207,34 +245,11 @@ pub fn detect_pitch_yin(samples: &[f32], sample_rate: f32) -> PitchResult {
     }
 
     // Step 4: Absolute threshold -- find the first dip below threshold
-    // starting from min_lag (to ignore frequencies above max_freq)
-    let mut best_tau = 0usize;
-    for tau in min_lag..=max_lag {
-        if cmnd[tau] < YIN_THRESHOLD {
-            // Walk forward to the local minimum of this valley
-            let mut t = tau;
-            while t + 1 <= max_lag && cmnd[t + 1] < cmnd[t] {
-                t += 1;
-            }
-            best_tau = t;
-            break;
-        }
-    }
-
-    // If no dip below threshold found, pick the global minimum
-    if best_tau == 0 {
-        let mut min_val = f32::MAX;
-        for tau in min_lag..=max_lag {
-            if cmnd[tau] < min_val {
-                min_val = cmnd[tau];
-                best_tau = tau;
-            }
-        }
-        // If the minimum is still very high, probably not a pitched signal
-        if min_val > 0.5 {
-            return PitchResult::silence();
-        }
-    }
+    // starting from min_lag (to ignore frequencies above max_freq), retrying
+    // with a relaxed threshold, then falling back to the global minimum
+    let Some(best_tau) = pick_tau(&cmnd, min_lag, max_lag) else {
+        return PitchResult::silence();
+    };
 
     // Step 5: Parabolic interpolation for sub-sample accuracy
     let tau_refined = if best_tau > 0 && best_tau < max_lag {
@@ -309,6 +324,47 @@ mod tests {
         assert!(error < 10.0, "Expected ~1047 Hz, got {} (error {})", result.hz, error);
     }
 
+    #[test]
+    fn test_recommended_window_by_register() {
+        let low = recommended_window(100.0, 44100.0);
+        let high = recommended_window(800.0, 44100.0);
+        assert!(low > high, "{} vs {}", low, high);
+        assert_eq!(low, 2048);
+        assert_eq!(high, 256);
+        // The suggested window is long enough to detect the pitch
+        let sample_rate = 44100.0;
+        let samples: Vec<f32> = (0..low)
+            .map(|i| 0.5 * (2.0 * core::f32::consts::PI * 100.0 * i as f32 / sample_rate).sin())
+            .collect();
+        let result = detect_pitch_yin(&samples, sample_rate);
+        assert!((result.hz - 100.0).abs() < 2.0, "Expected ~100 Hz, got {}", result.hz);
+        assert_eq!(recommended_window(0.0, 44100.0), recommended_window(80.0, 44100.0));
+    }
+
+    #[test]
+    fn test_relaxed_retry_prefers_fundamental() {
+        // Breathy note: the fundamental's valley (lag 100) only reaches 0.2,
+        // while the octave-below valley (lag 200) is deeper
+        let mut cmnd = vec![1.0f32; 301];
+        for (tau, v) in cmnd.iter_mut().enumerate() {
+            let (center, depth) = if tau < 150 { (100.0, 0.2) } else { (200.0, 0.18) };
+            let offset = (tau as f32 - center).abs();
+            if offset <= 10.0 {
+                *v = depth + 0.01 * offset;
+            }
+        }
+
+        assert_eq!(first_dip(&cmnd, 20, 300, YIN_THRESHOLD), None);
+        assert_eq!(first_dip(&cmnd, 20, 300, YIN_RETRY_THRESHOLD), Some(100));
+        assert_eq!(pick_tau(&cmnd, 20, 300), Some(100));
+
+        // Nothing below the retry threshold: the global minimum, if low enough
+        let shallow: Vec<f32> = cmnd.iter().map(|v| v + 0.2).collect();
+        assert_eq!(pick_tau(&shallow, 20, 300), Some(200));
+        let unpitched = vec![0.9f32; 301];
+        assert_eq!(pick_tau(&unpitched, 20, 300), None);
+    }
+
     #[test]
     fn test_yin_silence() {
         let samples = vec![0.0; 4410];
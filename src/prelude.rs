@@ -0,0 +1,39 @@
+//! Commonly used types and functions, re-exported for native (non-WASM) callers.
+//!
+//! ```
+//! use trumpet_rs::prelude::*;
+//!
+//! let score: Score = generate("major_scale", "C4", 120.0).unwrap();
+//! let played: Vec<PlayedNote> = score
+//!     .notes
+//!     .iter()
+//!     .filter(|n| !n.is_rest)
+//!     .map(|n| PlayedNote {
+//!         onset_beat: n.start_beat,
+//!         midi_float: n.midi as f64,
+//!         midi_rounded: n.midi,
+//!         confidence: 1.0,
+//!     })
+//!     .collect();
+//!
+//! let analysis: PerformanceAnalysis = analyze_performance(&score, &played, 50.0, 0.25);
+//! assert_eq!(analysis.notes_correct, analysis.total_notes);
+//!
+//! let silence = detect_pitch_yin(&[0.0; 2048], 44100.0);
+//! assert_eq!(silence.hz, 0.0);
+//! assert!(parse_musicxml("<score-partwise/>").is_ok());
+//! ```
+
+pub use crate::exercises::generators::{generate, generate_with_options};
+pub use crate::parser::musicxml::parse_musicxml;
+pub use crate::pitch::yin::{detect_pitch_yin, PitchDetector, PitchResult};
+pub use crate::scoring::analyzer::{
+    analyze_performance, analyze_performance_with_confidences, analyze_performance_with_options,
+    analyze_performance_with_trail, AnalyzerContext,
+};
+#[cfg(feature = "binary")]
+pub use crate::scoring::types::{analysis_from_bytes, analysis_to_bytes};
+pub use crate::scoring::types::{
+    AnalysisOptions, DynamicsTrailPoint, MeasureInfo, NoteEvent, NoteResult, PerformanceAnalysis,
+    PitchTrailPoint, PlayedNote, RestViolation, Score, TransposeInfo, TuningSystem, WedgeSpan,
+};
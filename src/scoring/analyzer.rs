@@ -1,5 +1,26 @@
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+#[cfg(not(feature = "std"))]
+use crate::math::FloatMath;
+use crate::scoring::stats::estimate_difficulty;
 use crate::scoring::types::*;
 
+/// Push a natural-language feedback message. The text is only built with the
+/// `std` feature; no_std builds report the numeric analysis alone.
+macro_rules! feedback {
+    ($list:expr, $fmt:literal $(, $arg:expr)* $(,)?) => {{
+        #[cfg(feature = "std")]
+        $list.push(format!($fmt $(, $arg)*));
+        #[cfg(not(feature = "std"))]
+        let _ = (&mut $list, $(&$arg),*);
+    }};
+}
+
 const NOTE_NAMES: [&str; 12] = [
     "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
 ];
@@ -10,8 +31,254 @@ fn midi_to_name(midi: i32) -> String {
     format!("{}{}", name, octave)
 }
 
-fn cents_between(played_midi: f64, target_midi: i32) -> f64 {
-    (played_midi - target_midi as f64) * 100.0
+/// Frequency of a (fractional) MIDI pitch given the A4 reference.
+fn midi_to_hz(midi: f64, reference_hz: f64) -> f64 {
+    reference_hz * 2.0f64.powf((midi - 69.0) / 12.0)
+}
+
+fn cents_between(played_midi: f64, target_midi: f64) -> f64 {
+    (played_midi - target_midi) * 100.0
+}
+
+/// Weight of a target's timing error: 0.0 in unmetered measures (timing is
+/// free), its beat strength when weighting is enabled and the measure is
+/// known, otherwise 1.0.
+fn timing_weight(measure: Option<&MeasureInfo>, target: &NoteEvent, options: &AnalysisOptions) -> f64 {
+    if measure.is_some_and(|m| m.unmetered) {
+        return 0.0;
+    }
+    if !options.weight_timing_by_beat {
+        return 1.0;
+    }
+    measure.map_or(1.0, |m| beat_strength(target.start_beat, m))
+}
+
+/// Minimum matching window for notes in unmetered (senza misura) measures.
+const UNMETERED_TIMING_TOLERANCE_BEATS: f64 = 1.0;
+
+/// Sight-reading mode widens the timing window by this factor.
+const SIGHT_READING_TIMING_SCALE: f64 = 2.0;
+
+/// Points subtracted from `overall_score` per re-attempt in sight-reading mode.
+const REATTEMPT_PENALTY: f64 = 5.0;
+
+/// A run of at least this many consecutive missed notes reads as the player
+/// having lost their place.
+const LOST_PLACE_MISSED_RUN: usize = 3;
+
+/// Longest run of consecutive missed notes as (start_index, length).
+fn longest_missed_run(note_results: &[NoteResult]) -> Option<(usize, usize)> {
+    let mut longest: Option<(usize, usize)> = None;
+    let mut start = 0;
+    for (i, result) in note_results.iter().enumerate() {
+        if result.status != "missed" {
+            start = i + 1;
+            continue;
+        }
+        let len = i + 1 - start;
+        if longest.is_none_or(|(_, best)| len > best) {
+            longest = Some((start, len));
+        }
+    }
+    longest
+}
+
+/// Cents beyond the tolerance over which a wrong-pitch note's credit fades
+/// from full to none.
+const PARTIAL_CREDIT_FALLOFF_CENTS: f64 = 50.0;
+
+/// Correctness credit for a note result: 1.0 when correct, fading linearly to
+/// 0.0 as a wrong pitch moves `PARTIAL_CREDIT_FALLOFF_CENTS` past the
+/// tolerance, 0.0 when missed.
+fn note_credit(result: &NoteResult, tolerance_cents: f64) -> f64 {
+    match (result.status.as_str(), result.pitch_error_cents) {
+        ("correct", _) => 1.0,
+        ("wrong_pitch", Some(error)) => {
+            let excess = error.abs() - tolerance_cents;
+            (1.0 - excess / PARTIAL_CREDIT_FALLOFF_CENTS).clamp(0.0, 1.0)
+        }
+        _ => 0.0,
+    }
+}
+
+/// Mean timing error of the opening third must exceed this (in beats) to
+/// count as a rushed or dragged entry...
+const RECOVERY_ENTRY_ERROR_BEATS: f64 = 0.15;
+/// ...and the closing third's must fall within this for a recovery.
+const RECOVERY_SETTLED_ERROR_BEATS: f64 = 0.05;
+
+/// Mean timing error of the opening third of the matched notes when the
+/// player rushed or dragged into the passage and settled by the closing
+/// third. Needs at least two timed notes per third.
+fn tempo_recovery(note_results: &[NoteResult]) -> Option<f64> {
+    let errors: Vec<f64> = note_results.iter().filter_map(|r| r.timing_error_beats).collect();
+    let third = errors.len() / 3;
+    if third < 2 {
+        return None;
+    }
+    let mean = |e: &[f64]| e.iter().sum::<f64>() / e.len() as f64;
+    let entry = mean(&errors[..third]);
+    let exit = mean(&errors[errors.len() - third..]);
+    (entry.abs() > RECOVERY_ENTRY_ERROR_BEATS && exit.abs() <= RECOVERY_SETTLED_ERROR_BEATS)
+        .then_some(entry)
+}
+
+/// Pitch a played note must land near to count as correct, honoring
+/// microtone snapping and the tuning system.
+fn graded_pitch(target: &NoteEvent, options: &AnalysisOptions) -> f64 {
+    if options.snap_microtones {
+        target.midi as f64 + options.tuning.offset_cents(target.midi) / 100.0
+    } else {
+        notated_pitch(target, options)
+    }
+}
+
+/// Exact pitch `target` asks for under the tuning system, which reported
+/// cents errors are measured from even when microtones are snapped.
+fn notated_pitch(target: &NoteEvent, options: &AnalysisOptions) -> f64 {
+    target.target_pitch() + options.tuning.offset_cents(target.midi) / 100.0
+}
+
+/// Frames below this confidence at the start of a segment are treated as attack.
+const ATTACK_CONFIDENCE: f64 = 0.8;
+
+/// A note's attack lasts until its trail first comes within this many
+/// cents of the target.
+const ATTACK_SETTLED_CENTS: f64 = 20.0;
+
+/// Number of leading trail points of a note before it settles within
+/// `ATTACK_SETTLED_CENTS` of `target_midi` (all of them if it never does).
+fn attack_point_count(points: &[&PitchTrailPoint], target_midi: f64) -> usize {
+    points
+        .iter()
+        .take_while(|p| ((p.midi_float - target_midi) * 100.0).abs() > ATTACK_SETTLED_CENTS)
+        .count()
+}
+
+/// Once settled, a note gliding into the next one has left when its trail
+/// strays this many cents from the target.
+const GLISSANDO_DEPARTURE_CENTS: f64 = 50.0;
+
+/// Trail points sounding during `target`. For a note that slides into the
+/// next (`glissando_to`), the points from where the trail leaves the target
+/// after settling are the sweep rather than the note, and are dropped.
+fn note_points<'t>(target: &NoteEvent, trail: &'t [PitchTrailPoint]) -> Vec<&'t PitchTrailPoint> {
+    let note_end = target.start_beat + target.duration_beats;
+    let mut points: Vec<&PitchTrailPoint> = trail
+        .iter()
+        .filter(|p| p.beat >= target.start_beat && p.beat < note_end)
+        .collect();
+    if target.glissando_to {
+        let target_midi = target.target_pitch();
+        let settled = attack_point_count(&points, target_midi);
+        let departure = points[settled..].iter().position(|p| {
+            ((p.midi_float - target_midi) * 100.0).abs() > GLISSANDO_DEPARTURE_CENTS
+        });
+        if let Some(departure) = departure {
+            points.truncate(settled + departure);
+        }
+    }
+    points
+}
+
+/// Representative pitch for a note segment: drops leading low-confidence
+/// attack frames (at most half the segment), then takes the
+/// confidence-weighted median of the steady portion. Returns 0.0 for an
+/// empty segment.
+pub fn segment_pitch(trail: &[PitchTrailPoint], confidences: &[f64]) -> f64 {
+    let len = trail.len().min(confidences.len());
+    if len == 0 {
+        return 0.0;
+    }
+
+    let attack = confidences[..len]
+        .iter()
+        .take_while(|&&c| c < ATTACK_CONFIDENCE)
+        .count()
+        .min(len / 2);
+
+    let mut frames: Vec<(f64, f64)> = trail[attack..len]
+        .iter()
+        .zip(&confidences[attack..len])
+        .map(|(p, &c)| (p.midi_float, c.max(0.0)))
+        .collect();
+    frames.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+
+    let total_weight: f64 = frames.iter().map(|f| f.1).sum();
+    if total_weight <= 0.0 {
+        return frames[frames.len() / 2].0;
+    }
+
+    let mut cumulative = 0.0;
+    for &(midi, weight) in &frames {
+        cumulative += weight;
+        if cumulative >= total_weight / 2.0 {
+            return midi;
+        }
+    }
+    frames[frames.len() - 1].0
+}
+
+/// Reference frequency for A4 implied by a sustained tuning note the player
+/// intends as `assumed_midi`: the trail's median pitch offset from that note,
+/// applied to 440 Hz. Returns 440.0 for an empty trail.
+pub fn calibrate_reference(trail: &[PitchTrailPoint], assumed_midi: i32) -> f64 {
+    if trail.is_empty() {
+        return 440.0;
+    }
+    let played = segment_pitch(trail, &vec![1.0; trail.len()]);
+    440.0 * 2.0f64.powf((played - assumed_midi as f64) / 12.0)
+}
+
+/// Plot-ready intonation series: for each trail point, its beat and the cents
+/// offset from the target sounding at that beat (the nearest one when a chord
+/// is active), or `None` when no target is sounding.
+pub fn cents_trail(trail: &[PitchTrailPoint], score: &Score) -> Vec<(f64, Option<f64>)> {
+    trail
+        .iter()
+        .map(|p| {
+            let cents = score
+                .notes
+                .iter()
+                .filter(|n| {
+                    n.is_played() && p.beat >= n.start_beat && p.beat < n.start_beat + n.duration_beats
+                })
+                .map(|n| cents_between(p.midi_float, n.target_pitch()))
+                .min_by(|a, b| a.abs().partial_cmp(&b.abs()).unwrap_or(Ordering::Equal));
+            (p.beat, cents)
+        })
+        .collect()
+}
+
+/// Metric strength of a beat within its measure: 1.0 on the downbeat,
+/// 0.75 on the secondary strong beat (mid-measure in even meters, each
+/// dotted group in compound meters), 0.5 on other beats, 0.25 off the beat.
+pub fn beat_strength(beat: f64, measure: &MeasureInfo) -> f64 {
+    let unit = 4.0 / measure.time_sig_den.max(1) as f64;
+    let pos = (beat - measure.start_beat) / unit;
+    let nearest = pos.round();
+    if (pos - nearest).abs() > 1e-6 {
+        return 0.25;
+    }
+    let pos = nearest as i64;
+    let num = measure.time_sig_num as i64;
+    if pos == 0 {
+        return 1.0;
+    }
+    let compound = measure.time_sig_den == 8 && num > 3 && num % 3 == 0;
+    if (compound && pos % 3 == 0) || (!compound && num % 2 == 0 && pos == num / 2) {
+        0.75
+    } else {
+        0.5
+    }
+}
+
+fn weighted_mean(values: &[f64], weights: &[f64]) -> f64 {
+    let total: f64 = weights.iter().sum();
+    if total <= 0.0 {
+        return 0.0;
+    }
+    values.iter().zip(weights).map(|(v, w)| v * w).sum::<f64>() / total
 }
 
 pub fn analyze_performance(
@@ -30,297 +297,1211 @@ pub fn analyze_performance_with_trail(
     timing_tolerance_beats: f64,
     pitch_trail: Option<&[PitchTrailPoint]>,
 ) -> PerformanceAnalysis {
-    let target_notes: Vec<&NoteEvent> = score.notes.iter().filter(|n| !n.is_rest).collect();
-    let total_notes = target_notes.len() as u32;
-
-    if total_notes == 0 {
-        return PerformanceAnalysis {
-            total_notes: 0,
-            notes_correct: 0,
-            notes_wrong_pitch: 0,
-            notes_missed: 0,
-            avg_pitch_error_cents: 0.0,
-            avg_timing_error_beats: 0.0,
-            pitch_tendency: "accurate".to_string(),
-            timing_tendency: "on_time".to_string(),
-            problem_intervals: Vec::new(),
-            feedback: vec!["No notes in score to analyze.".to_string()],
-            overall_score: 0.0,
-            note_results: Vec::new(),
-            pitch_stability: None,
-            attack_quality: None,
-            breath_support: None,
-            endurance_delta: None,
-            technique_feedback: Vec::new(),
-        };
-    }
-
-    let mut note_results: Vec<NoteResult> = Vec::new();
-    let mut pitch_errors: Vec<f64> = Vec::new();
-    let mut timing_errors: Vec<f64> = Vec::new();
-    let mut used_played: Vec<bool> = vec![false; played_notes.len()];
-
-    // For each target note, find the best matching played note
-    for target in &target_notes {
-        let mut best_idx: Option<usize> = None;
-        let mut best_timing_dist = f64::MAX;
-
-        for (i, played) in played_notes.iter().enumerate() {
-            if used_played[i] {
-                continue;
-            }
-            let timing_dist = (played.onset_beat - target.start_beat).abs();
-            if timing_dist <= timing_tolerance_beats && timing_dist < best_timing_dist {
-                best_timing_dist = timing_dist;
-                best_idx = Some(i);
-            }
+    analyze_performance_with_options(
+        score,
+        played_notes,
+        tolerance_cents,
+        timing_tolerance_beats,
+        pitch_trail,
+        &AnalysisOptions::default(),
+    )
+}
+
+pub fn analyze_performance_with_options(
+    score: &Score,
+    played_notes: &[PlayedNote],
+    tolerance_cents: f64,
+    timing_tolerance_beats: f64,
+    pitch_trail: Option<&[PitchTrailPoint]>,
+    options: &AnalysisOptions,
+) -> PerformanceAnalysis {
+    analyze_performance_with_confidences(
+        score,
+        played_notes,
+        tolerance_cents,
+        timing_tolerance_beats,
+        pitch_trail,
+        &[],
+        options,
+    )
+}
+
+/// Full analysis. `trail_confidences` holds the detector confidence of each
+/// `pitch_trail` point (parallel to it, as in `segment_pitch`) and enables
+/// airy-note detection; pass an empty slice when unavailable.
+pub fn analyze_performance_with_confidences(
+    score: &Score,
+    played_notes: &[PlayedNote],
+    tolerance_cents: f64,
+    timing_tolerance_beats: f64,
+    pitch_trail: Option<&[PitchTrailPoint]>,
+    trail_confidences: &[f64],
+    options: &AnalysisOptions,
+) -> PerformanceAnalysis {
+    AnalyzerContext::new(score).analyze(
+        played_notes,
+        tolerance_cents,
+        timing_tolerance_beats,
+        pitch_trail,
+        trail_confidences,
+        options,
+    )
+}
+
+/// Per-score state for repeated analysis (e.g. live scoring many times per
+/// second): target notes and measure lookups are extracted once in `new`
+/// and reused by every `analyze` call.
+#[derive(Clone, Debug)]
+pub struct AnalyzerContext<'a> {
+    score: &'a Score,
+    targets: Vec<&'a NoteEvent>,
+    measures: BTreeMap<u32, &'a MeasureInfo>,
+}
+
+impl<'a> AnalyzerContext<'a> {
+    pub fn new(score: &'a Score) -> Self {
+        let mut measures = BTreeMap::new();
+        for measure in &score.measures {
+            measures.entry(measure.number).or_insert(measure);
+        }
+        AnalyzerContext {
+            score,
+            targets: score.notes.iter().filter(|n| n.is_played()).collect(),
+            measures,
         }
+    }
 
-        match best_idx {
-            Some(idx) => {
-                used_played[idx] = true;
-                let played = &played_notes[idx];
-                let cent_error = cents_between(played.midi_float, target.midi);
-                let timing_error = played.onset_beat - target.start_beat;
+    /// Played notes of the score (no rests or accompaniment), in order.
+    pub fn targets(&self) -> &[&'a NoteEvent] {
+        &self.targets
+    }
 
-                if cent_error.abs() <= tolerance_cents {
-                    note_results.push(NoteResult {
-                        target_midi: target.midi,
-                        target_beat: target.start_beat,
-                        status: "correct".to_string(),
-                        played_midi: Some(played.midi_float),
-                        pitch_error_cents: Some(cent_error),
-                        timing_error_beats: Some(timing_error),
-                    });
-                    pitch_errors.push(cent_error);
-                    timing_errors.push(timing_error);
+    fn measure_of(&self, target: &NoteEvent) -> Option<&'a MeasureInfo> {
+        self.measures.get(&target.measure_number).copied()
+    }
+
+    /// Same as `analyze_performance_with_confidences` on the context's score.
+    pub fn analyze(
+        &self,
+        played_notes: &[PlayedNote],
+        tolerance_cents: f64,
+        timing_tolerance_beats: f64,
+        pitch_trail: Option<&[PitchTrailPoint]>,
+        trail_confidences: &[f64],
+        options: &AnalysisOptions,
+    ) -> PerformanceAnalysis {
+        let score = self.score;
+        let target_notes = &self.targets;
+
+        let compensated: Vec<PlayedNote>;
+        let played_notes = if options.latency_beats != 0.0 {
+            compensated = played_notes
+                .iter()
+                .map(|p| PlayedNote {
+                    onset_beat: p.onset_beat - options.latency_beats,
+                    ..p.clone()
+                })
+                .collect();
+            &compensated[..]
+        } else {
+            played_notes
+        };
+        let warmup = options.warmup_notes.min(target_notes.len());
+        let total_notes = (target_notes.len() - warmup) as u32;
+
+        if total_notes == 0 {
+            return PerformanceAnalysis {
+                total_notes: 0,
+                notes_correct: 0,
+                notes_wrong_pitch: 0,
+                notes_missed: 0,
+                avg_pitch_error_cents: 0.0,
+                avg_timing_error_beats: 0.0,
+                pitch_tendency: "accurate".to_string(),
+                timing_tendency: "on_time".to_string(),
+                problem_intervals: Vec::new(),
+                timing_problem_intervals: Vec::new(),
+                feedback: vec!["No notes in score to analyze.".to_string()],
+                overall_score: 0.0,
+                note_results: Vec::new(),
+                pitch_stability: None,
+                attack_quality: None,
+                breath_support: None,
+                endurance_delta: None,
+                centering_cents: None,
+                forcing_detected: false,
+                technique_feedback: Vec::new(),
+                articulation_feedback: Vec::new(),
+                reattempts: 0,
+                tempo_recovered: false,
+                played_notes_count: played_notes.len() as u32,
+                silence_ratio: 0.0,
+            };
+        }
+
+        let mut note_results: Vec<NoteResult> = Vec::new();
+        let mut pitch_errors: Vec<f64> = Vec::new();
+        let mut timing_errors: Vec<f64> = Vec::new();
+        let mut timing_weights: Vec<f64> = Vec::new();
+        let mut used_played: Vec<bool> = vec![false; played_notes.len()];
+        let mut reattempts = 0u32;
+        let reference_hz = options.reference_hz.unwrap_or(440.0);
+        let timing_tolerance_beats = if options.sight_reading {
+            timing_tolerance_beats * SIGHT_READING_TIMING_SCALE
+        } else {
+            timing_tolerance_beats
+        };
+
+        // For each target note, find the best matching played note
+        for (i, target) in target_notes.iter().enumerate() {
+            let mut best_idx: Option<usize> = None;
+            let mut best_timing_dist = f64::MAX;
+            let timing_tolerance_beats = if self.measure_of(target).is_some_and(|m| m.unmetered) {
+                timing_tolerance_beats.max(UNMETERED_TIMING_TOLERANCE_BEATS)
+            } else {
+                timing_tolerance_beats
+            };
+
+            for (i, played) in played_notes.iter().enumerate() {
+                if used_played[i] {
+                    continue;
+                }
+                // Pitch-only takes the earliest note left, wherever it falls
+                if options.pitch_only {
+                    if played.onset_beat < best_timing_dist {
+                        best_timing_dist = played.onset_beat;
+                        best_idx = Some(i);
+                    }
+                    continue;
+                }
+                let timing_dist = (played.onset_beat - target.start_beat).abs();
+                // Sight-reading takes the first attempt, not the closest
+                let rank = if options.sight_reading {
+                    played.onset_beat
                 } else {
+                    timing_dist
+                };
+                if timing_dist <= timing_tolerance_beats && rank < best_timing_dist {
+                    best_timing_dist = rank;
+                    best_idx = Some(i);
+                }
+            }
+
+            // Ornaments legitimately add notes, so they never count as re-attempts
+            let check_reattempts =
+                options.sight_reading && !options.pitch_only && target.ornaments.is_empty();
+            if let (true, Some(first)) = (check_reattempts, best_idx) {
+                // Later notes nearer this target than the next are re-attempts
+                let next_beat = target_notes.get(i + 1).map(|n| n.start_beat);
+                for (j, played) in played_notes.iter().enumerate() {
+                    let dist = (played.onset_beat - target.start_beat).abs();
+                    if used_played[j]
+                        || j == first
+                        || played.onset_beat <= played_notes[first].onset_beat
+                        || dist > timing_tolerance_beats
+                        || next_beat.is_some_and(|b| (played.onset_beat - b).abs() <= dist)
+                    {
+                        continue;
+                    }
+                    used_played[j] = true;
+                    if i >= warmup {
+                        reattempts += 1;
+                    }
+                }
+            }
+
+            if i < warmup {
+                // Settling notes still claim their played note but are not scored
+                if let Some(idx) = best_idx {
+                    used_played[idx] = true;
+                }
+                continue;
+            }
+
+            let target_hz = midi_to_hz(notated_pitch(target, options), reference_hz);
+            match best_idx {
+                Some(idx) => {
+                    used_played[idx] = true;
+                    let onset = played_notes[idx].onset_beat;
+                    let graded = graded_pitch(target, options);
+                    // An ornament's extra notes belong to it: absorb those within
+                    // the note (leaving the next target's window) and grade the
+                    // one nearest the written pitch
+                    let mut idx = idx;
+                    if !options.pitch_only && !target.ornaments.is_empty() {
+                        let span_end =
+                            target.start_beat + target.duration_beats - timing_tolerance_beats;
+                        for (j, extra) in played_notes.iter().enumerate() {
+                            if used_played[j]
+                                || extra.onset_beat < onset
+                                || extra.onset_beat >= span_end
+                            {
+                                continue;
+                            }
+                            used_played[j] = true;
+                            let distance = (extra.midi_float - graded).abs();
+                            if distance < (played_notes[idx].midi_float - graded).abs() {
+                                idx = j;
+                            }
+                        }
+                    }
+                    let played = &played_notes[idx];
+                    let cent_error =
+                        cents_between(played.midi_float, notated_pitch(target, options));
+                    let timing_error = (!options.pitch_only).then_some(onset - target.start_beat);
+                    if let Some(timing_error) = timing_error {
+                        timing_errors.push(timing_error);
+                        let measure = self.measure_of(target);
+                        timing_weights.push(timing_weight(measure, target, options));
+                    }
+
+                    if cents_between(played.midi_float, graded).abs() <= tolerance_cents {
+                        note_results.push(NoteResult {
+                            target_midi: target.midi,
+                            target_beat: target.start_beat,
+                            status: "correct".to_string(),
+                            played_midi: Some(played.midi_float),
+                            pitch_error_cents: Some(cent_error),
+                            timing_error_beats: timing_error,
+                            target_hz,
+                            played_hz: Some(midi_to_hz(played.midi_float, reference_hz)),
+                        });
+                        pitch_errors.push(cent_error);
+                    } else {
+                        note_results.push(NoteResult {
+                            target_midi: target.midi,
+                            target_beat: target.start_beat,
+                            status: "wrong_pitch".to_string(),
+                            played_midi: Some(played.midi_float),
+                            pitch_error_cents: Some(cent_error),
+                            timing_error_beats: timing_error,
+                            target_hz,
+                            played_hz: Some(midi_to_hz(played.midi_float, reference_hz)),
+                        });
+                        pitch_errors.push(cent_error);
+                    }
+                }
+                None => {
                     note_results.push(NoteResult {
                         target_midi: target.midi,
                         target_beat: target.start_beat,
-                        status: "wrong_pitch".to_string(),
-                        played_midi: Some(played.midi_float),
-                        pitch_error_cents: Some(cent_error),
-                        timing_error_beats: Some(timing_error),
+                        status: "missed".to_string(),
+                        played_midi: None,
+                        pitch_error_cents: None,
+                        timing_error_beats: None,
+                        target_hz,
+                        played_hz: None,
                     });
-                    pitch_errors.push(cent_error);
-                    timing_errors.push(timing_error);
                 }
             }
-            None => {
-                note_results.push(NoteResult {
-                    target_midi: target.midi,
-                    target_beat: target.start_beat,
-                    status: "missed".to_string(),
-                    played_midi: None,
-                    pitch_error_cents: None,
-                    timing_error_beats: None,
-                });
-            }
         }
-    }
 
-    let notes_correct = note_results.iter().filter(|r| r.status == "correct").count() as u32;
-    let notes_wrong_pitch = note_results
-        .iter()
-        .filter(|r| r.status == "wrong_pitch")
-        .count() as u32;
-    let notes_missed = note_results.iter().filter(|r| r.status == "missed").count() as u32;
+        let notes_correct = note_results.iter().filter(|r| r.status == "correct").count() as u32;
+        let notes_wrong_pitch = note_results
+            .iter()
+            .filter(|r| r.status == "wrong_pitch")
+            .count() as u32;
+        let notes_missed = note_results.iter().filter(|r| r.status == "missed").count() as u32;
 
-    let avg_pitch_error_cents = if !pitch_errors.is_empty() {
-        pitch_errors.iter().sum::<f64>() / pitch_errors.len() as f64
-    } else {
-        0.0
-    };
+        let avg_pitch_error_cents = if !pitch_errors.is_empty() {
+            pitch_errors.iter().sum::<f64>() / pitch_errors.len() as f64
+        } else {
+            0.0
+        };
 
-    let avg_timing_error_beats = if !timing_errors.is_empty() {
-        timing_errors.iter().sum::<f64>() / timing_errors.len() as f64
-    } else {
-        0.0
-    };
+        let avg_timing_error_beats = if !timing_errors.is_empty() {
+            weighted_mean(&timing_errors, &timing_weights)
+        } else {
+            0.0
+        };
 
-    let pitch_tendency = if avg_pitch_error_cents > 10.0 {
-        "sharp"
-    } else if avg_pitch_error_cents < -10.0 {
-        "flat"
-    } else {
-        "accurate"
-    }
-    .to_string();
+        let pitch_tendency = if avg_pitch_error_cents > 10.0 {
+            "sharp"
+        } else if avg_pitch_error_cents < -10.0 {
+            "flat"
+        } else {
+            "accurate"
+        }
+        .to_string();
 
-    let timing_tendency = if avg_timing_error_beats > 0.1 {
-        "late"
-    } else if avg_timing_error_beats < -0.1 {
-        "early"
-    } else {
-        "on_time"
-    }
-    .to_string();
+        let timing_tendency = if avg_timing_error_beats > 0.1 {
+            "late"
+        } else if avg_timing_error_beats < -0.1 {
+            "early"
+        } else {
+            "on_time"
+        }
+        .to_string();
 
-    // Analyze interval problems
-    let problem_intervals = analyze_intervals(&target_notes, &note_results, tolerance_cents);
+        // Analyze interval problems
+        let (problem_intervals, timing_problem_intervals) =
+            analyze_intervals(&target_notes[warmup..], &note_results, tolerance_cents);
 
-    // Generate feedback messages
-    let mut feedback: Vec<String> = Vec::new();
+        // Generate feedback messages
+        let mut feedback: Vec<String> = Vec::new();
 
-    if total_notes > 0 {
-        let pct = (notes_correct as f64 / total_notes as f64) * 100.0;
-        if pct >= 90.0 {
-            feedback.push(format!("Excellent! You nailed {:.0}% of the notes.", pct));
-        } else if pct >= 70.0 {
-            feedback.push(format!("Good job! You got {:.0}% of the notes right.", pct));
-        } else if pct >= 50.0 {
-            feedback.push(format!(
-                "Keep practicing! You hit {:.0}% of the notes correctly.",
-                pct
-            ));
-        } else {
-            feedback.push(format!(
-                "This one's tough! You got {:.0}% correct. Try slowing down the tempo.",
-                pct
-            ));
+        if total_notes > 0 {
+            let pct = (notes_correct as f64 / total_notes as f64) * 100.0;
+            if pct >= 90.0 {
+                feedback!(feedback, "Excellent! You nailed {:.0}% of the notes.", pct);
+            } else if pct >= 70.0 {
+                feedback!(feedback, "Good job! You got {:.0}% of the notes right.", pct);
+            } else if pct >= 50.0 {
+                feedback!(
+                    feedback,
+                    "Keep practicing! You hit {:.0}% of the notes correctly.",
+                    pct
+                );
+            } else {
+                feedback!(
+                    feedback,
+                    "This one's tough! You got {:.0}% correct. Try slowing down the tempo.",
+                    pct
+                );
+            }
         }
-    }
 
-    if notes_missed > 0 {
-        feedback.push(format!(
-            "You missed {} note{}. Make sure to play through the whole piece.",
-            notes_missed,
-            if notes_missed == 1 { "" } else { "s" }
-        ));
-    }
-
-    if !pitch_errors.is_empty() {
-        let abs_avg = pitch_errors.iter().map(|e| e.abs()).sum::<f64>() / pitch_errors.len() as f64;
-        if abs_avg > 30.0 {
-            if avg_pitch_error_cents > 10.0 {
-                feedback.push(format!(
-                    "Your pitch is consistently {:.0} cents sharp. Try relaxing your embouchure slightly.",
-                    avg_pitch_error_cents
-                ));
-            } else if avg_pitch_error_cents < -10.0 {
-                feedback.push(format!(
-                    "Your pitch is consistently {:.0} cents flat. Try firming up your embouchure and using more air support.",
-                    avg_pitch_error_cents.abs()
-                ));
+        let lost_place =
+            longest_missed_run(&note_results).filter(|&(_, len)| len >= LOST_PLACE_MISSED_RUN);
+        if let Some((start, len)) = lost_place {
+            feedback!(
+                feedback,
+                "You missed {} notes in a row starting in measure {} — you may have lost your place. Keep your eyes a bar ahead and keep going.",
+                len,
+                target_notes[warmup + start].measure_number
+            );
+        } else if notes_missed > 0 {
+            feedback!(
+                feedback,
+                "You missed {} note{}. Make sure to play through the whole piece.",
+                notes_missed,
+                if notes_missed == 1 { "" } else { "s" }
+            );
+        }
+
+        if !pitch_errors.is_empty() {
+            let abs_avg = pitch_errors.iter().map(|e| e.abs()).sum::<f64>() / pitch_errors.len() as f64;
+            if abs_avg > 30.0 {
+                if avg_pitch_error_cents > 10.0 {
+                    feedback!(
+                        feedback,
+                        "Your pitch is consistently {:.0} cents sharp. Try relaxing your embouchure slightly.",
+                        avg_pitch_error_cents
+                    );
+                } else if avg_pitch_error_cents < -10.0 {
+                    feedback!(
+                        feedback,
+                        "Your pitch is consistently {:.0} cents flat. Try firming up your embouchure and using more air support.",
+                        avg_pitch_error_cents.abs()
+                    );
+                }
             }
         }
-    }
 
-    if !timing_errors.is_empty() {
-        let abs_avg =
-            timing_errors.iter().map(|e| e.abs()).sum::<f64>() / timing_errors.len() as f64;
-        if abs_avg > 0.15 {
-            if avg_timing_error_beats > 0.1 {
-                feedback.push(
-                    "You tend to come in late. Try anticipating the beat and starting your air a bit earlier.".to_string(),
+        if !timing_errors.is_empty() {
+            let abs_errors: Vec<f64> = timing_errors.iter().map(|e| e.abs()).collect();
+            let abs_avg = weighted_mean(&abs_errors, &timing_weights);
+            if abs_avg > 0.15 {
+                if avg_timing_error_beats > 0.1 {
+                    feedback!(
+                        feedback,
+                        "You tend to come in late. Try anticipating the beat and starting your air a bit earlier."
+                    );
+                } else if avg_timing_error_beats < -0.1 {
+                    feedback!(
+                        feedback,
+                        "You tend to rush ahead. Try listening to the beat and holding back slightly."
+                    );
+                }
+            }
+        }
+
+        let recovery = tempo_recovery(&note_results);
+        if let Some(entry) = recovery {
+            feedback!(
+                feedback,
+                "Good recovery: you {} at the start but settled onto the beat by the end.",
+                if entry < 0.0 { "rushed" } else { "dragged" }
+            );
+        }
+
+        for problem in &problem_intervals {
+            let dir_word = if problem.direction == "up" {
+                "ascending"
+            } else {
+                "descending"
+            };
+            if problem.avg_error_cents > 0.0 {
+                feedback!(
+                    feedback,
+                    "You overshoot when going {} from {} to {} (avg +{:.0} cents). Try less pressure on the jump.",
+                    dir_word, problem.from_note, problem.to_note, problem.avg_error_cents
                 );
-            } else if avg_timing_error_beats < -0.1 {
-                feedback.push(
-                    "You tend to rush ahead. Try listening to the beat and holding back slightly."
-                        .to_string(),
+            } else {
+                feedback!(
+                    feedback,
+                    "You undershoot when going {} from {} to {} (avg {:.0} cents). Use more air support on the jump.",
+                    dir_word, problem.from_note, problem.to_note, problem.avg_error_cents
                 );
             }
         }
-    }
 
-    for problem in &problem_intervals {
-        let dir_word = if problem.direction == "up" {
-            "ascending"
+        for problem in &timing_problem_intervals {
+            feedback!(
+                feedback,
+                "You come in {} after the jump from {} to {} (avg {:+.2} beats). Hear the next note before you play it.",
+                if problem.avg_timing_error_beats > 0.0 { "late" } else { "early" },
+                problem.from_note,
+                problem.to_note,
+                problem.avg_timing_error_beats
+            );
+        }
+
+        if reattempts > 0 {
+            feedback!(
+                feedback,
+                "You replayed {} note{}. When sight-reading, keep going rather than fixing mistakes.",
+                reattempts,
+                if reattempts == 1 { "" } else { "s" }
+            );
+        }
+
+        if feedback.is_empty() {
+            feedback!(feedback, "Play with the mic active to get feedback!");
+        }
+
+        // Overall score: weighted combination of pitch accuracy and note hit rate
+        let hit_rate = if total_notes > 0 {
+            (notes_correct + notes_wrong_pitch) as f64 / total_notes as f64
+        } else {
+            0.0
+        };
+        let pitch_score = if !pitch_errors.is_empty() {
+            let abs_avg = pitch_errors.iter().map(|e| e.abs()).sum::<f64>() / pitch_errors.len() as f64;
+            (1.0 - (abs_avg / 100.0).min(1.0)) * 100.0
+        } else {
+            0.0
+        };
+        // Near misses earn partial credit so the score has no cliff at the tolerance
+        let correct_rate = if total_notes > 0 {
+            note_results.iter().map(|r| note_credit(r, tolerance_cents)).sum::<f64>() / total_notes as f64
         } else {
-            "descending"
+            0.0
         };
-        if problem.avg_error_cents > 0.0 {
-            feedback.push(format!(
-                "You overshoot when going {} from {} to {} (avg +{:.0} cents). Try less pressure on the jump.",
-                dir_word, problem.from_note, problem.to_note, problem.avg_error_cents
-            ));
+        let rest_violations = if options.pitch_only {
+            0
         } else {
-            feedback.push(format!(
-                "You undershoot when going {} from {} to {} (avg {:.0} cents). Use more air support on the jump.",
-                dir_word, problem.from_note, problem.to_note, problem.avg_error_cents
-            ));
+            count_rest_violations(score, played_notes, &used_played)
+        };
+        let overall_score = (correct_rate * 60.0 + hit_rate * 20.0 + pitch_score * 0.2
+            - rest_violations as f64 * options.rest_penalty
+            - reattempts as f64 * REATTEMPT_PENALTY)
+            .clamp(0.0, 100.0);
+
+        // Technique analysis
+        let (pitch_stability, attack_quality, breath_support, endurance_delta, mut technique_feedback) =
+            if let Some(trail) = pitch_trail {
+                analyze_technique(&target_notes[warmup..], &note_results, trail, options)
+            } else {
+                (None, None, None, None, Vec::new())
+            };
+        let mut forced = 0;
+        if let Some(trail) = pitch_trail {
+            let airy = count_airy_notes(&target_notes[warmup..], &note_results, trail, trail_confidences);
+            if airy > 0 {
+                feedback!(
+                    technique_feedback,
+                    "{} note{} sounded airy. Support the sound with more air and a firm embouchure.",
+                    airy,
+                    if airy == 1 { "" } else { "s" }
+                );
+            }
+            forced = count_forced_notes(&target_notes[warmup..], &note_results, trail, options);
+            if forced > 0 {
+                feedback!(
+                    technique_feedback,
+                    "{} high note{} sounded forced: sharp and unsteady. Let the air do the work instead of mouthpiece pressure.",
+                    forced,
+                    if forced == 1 { "" } else { "s" }
+                );
+            }
         }
-    }
+        let articulation_feedback = match pitch_trail {
+            Some(trail) => analyze_articulation(&target_notes[warmup..], &note_results, trail),
+            None => Vec::new(),
+        };
+        let centering_cents = pitch_trail
+            .and_then(|trail| centering(&target_notes[warmup..], &note_results, trail, options));
 
-    if feedback.is_empty() {
-        feedback.push("Play with the mic active to get feedback!".to_string());
+        PerformanceAnalysis {
+            total_notes,
+            notes_correct,
+            notes_wrong_pitch,
+            notes_missed,
+            avg_pitch_error_cents,
+            avg_timing_error_beats,
+            pitch_tendency,
+            timing_tendency,
+            problem_intervals,
+            timing_problem_intervals,
+            feedback,
+            overall_score,
+            note_results,
+            pitch_stability,
+            attack_quality,
+            breath_support,
+            endurance_delta,
+            centering_cents,
+            forcing_detected: forced > 0,
+            technique_feedback,
+            articulation_feedback,
+            reattempts,
+            tempo_recovered: recovery.is_some(),
+            played_notes_count: played_notes.len() as u32,
+            silence_ratio: silence_ratio(&target_notes[warmup..], played_notes, pitch_trail),
+        }
     }
+}
 
-    // Overall score: weighted combination of pitch accuracy and note hit rate
-    let hit_rate = if total_notes > 0 {
-        (notes_correct + notes_wrong_pitch) as f64 / total_notes as f64
-    } else {
-        0.0
+/// Analyze a drill of measures `first..=last` in isolation: the score is
+/// sliced with `Score::slice_measures`, and `played_notes` must be rebased so
+/// beat 0 is the downbeat of `first`.
+pub fn analyze_measure_range(
+    score: &Score,
+    first: u32,
+    last: u32,
+    played_notes: &[PlayedNote],
+    tolerance_cents: f64,
+    timing_tolerance_beats: f64,
+) -> PerformanceAnalysis {
+    let slice = score.slice_measures(first, last);
+    analyze_performance(&slice, played_notes, tolerance_cents, timing_tolerance_beats)
+}
+
+/// Score a single measure for a practice-one-measure loop. Only that
+/// measure's notes are targets; `played_notes` keep their whole-score beats,
+/// so a take of the full piece can be graded one measure at a time.
+pub fn analyze_measure(
+    score: &Score,
+    played_notes: &[PlayedNote],
+    measure: u32,
+    tolerance_cents: f64,
+    timing_tolerance_beats: f64,
+) -> PerformanceAnalysis {
+    let targets = Score {
+        tempo: score.tempo,
+        notes: score
+            .notes
+            .iter()
+            .filter(|n| n.measure_number == measure)
+            .cloned()
+            .collect(),
+        measures: score.measures.clone(),
+        key_fifths: score.key_fifths,
+        transpose: score.transpose.clone(),
+        title: score.title.clone(),
+        total_beats: score.total_beats,
+        wedges: score.wedges.clone(),
     };
-    let pitch_score = if !pitch_errors.is_empty() {
-        let abs_avg = pitch_errors.iter().map(|e| e.abs()).sum::<f64>() / pitch_errors.len() as f64;
-        (1.0 - (abs_avg / 100.0).min(1.0)) * 100.0
-    } else {
-        0.0
+    analyze_performance(&targets, played_notes, tolerance_cents, timing_tolerance_beats)
+}
+
+/// Grade a student take against a recorded reference take (e.g. the
+/// teacher's) instead of the score. Each reference note becomes a target at
+/// its exact played pitch, lasting until the next reference onset; the
+/// student take is shifted so both takes start together.
+pub fn compare_performances(
+    reference: &[PlayedNote],
+    student: &[PlayedNote],
+    tolerance_cents: f64,
+    timing_tolerance_beats: f64,
+) -> PerformanceAnalysis {
+    let mut reference: Vec<&PlayedNote> = reference.iter().collect();
+    reference.sort_by(|a, b| a.onset_beat.partial_cmp(&b.onset_beat).unwrap_or(Ordering::Equal));
+
+    let notes: Vec<NoteEvent> = reference
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            let end = reference.get(i + 1).map_or(p.onset_beat + 1.0, |next| next.onset_beat);
+            let midi = p.midi_float.round() as i32;
+            NoteEvent {
+                start_beat: p.onset_beat,
+                duration_beats: end - p.onset_beat,
+                midi,
+                is_rest: false,
+                measure_number: (p.onset_beat.max(0.0) / 4.0) as u32 + 1,
+                note_type: String::new(),
+                microtone_cents: (p.midi_float - midi as f64) * 100.0,
+                articulations: Vec::new(),
+                beam_group: None,
+                dynamics: None,
+                velocity: None,
+                ornaments: Vec::new(),
+                accidental: None,
+                glissando_to: false,
+                accompaniment: false,
+            }
+        })
+        .collect();
+    let total_beats = notes.last().map_or(0.0, |n| n.start_beat + n.duration_beats);
+    let targets = Score {
+        tempo: 120.0,
+        notes,
+        measures: Vec::new(),
+        key_fifths: 0,
+        transpose: None,
+        title: None,
+        total_beats,
+        wedges: Vec::new(),
     };
-    let correct_rate = if total_notes > 0 {
-        notes_correct as f64 / total_notes as f64
-    } else {
-        0.0
+
+    let student_start = student.iter().map(|p| p.onset_beat).reduce(f64::min);
+    let options = AnalysisOptions {
+        latency_beats: match (student_start, reference.first()) {
+            (Some(start), Some(first)) => start - first.onset_beat,
+            _ => 0.0,
+        },
+        ..AnalysisOptions::default()
     };
-    let overall_score = (correct_rate * 60.0 + hit_rate * 20.0 + pitch_score * 0.2).min(100.0);
+    analyze_performance_with_options(
+        &targets,
+        student,
+        tolerance_cents,
+        timing_tolerance_beats,
+        None,
+        &options,
+    )
+}
 
-    // Technique analysis
-    let (pitch_stability, attack_quality, breath_support, endurance_delta, technique_feedback) =
-        if let Some(trail) = pitch_trail {
-            analyze_technique(&target_notes, &note_results, trail)
-        } else {
-            (None, None, None, None, Vec::new())
-        };
-
-    PerformanceAnalysis {
-        total_notes,
-        notes_correct,
-        notes_wrong_pitch,
-        notes_missed,
-        avg_pitch_error_cents,
-        avg_timing_error_beats,
-        pitch_tendency,
-        timing_tendency,
-        problem_intervals,
-        feedback,
-        overall_score,
-        note_results,
-        pitch_stability,
-        attack_quality,
-        breath_support,
-        endurance_delta,
-        technique_feedback,
+/// Articulation speed and evenness of a (typically repeated-note) passage,
+/// from the played onsets converted to seconds at the score's tempo.
+/// Fewer than two onsets yield an all-zero report.
+pub fn articulation_rate(played: &[PlayedNote], score: &Score) -> ArticulationReport {
+    let seconds_per_beat = 60.0 / score.tempo;
+    let mut onsets: Vec<f64> = played.iter().map(|p| p.onset_beat * seconds_per_beat).collect();
+    onsets.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+
+    let intervals: Vec<f64> = onsets.windows(2).map(|w| w[1] - w[0]).collect();
+    if intervals.is_empty() {
+        return ArticulationReport {
+            notes_per_second: 0.0,
+            mean_interval_seconds: 0.0,
+            interval_std_dev_seconds: 0.0,
+        };
     }
-}
 
-fn analyze_technique(
-    target_notes: &[&NoteEvent],
-    note_results: &[NoteResult],
-    pitch_trail: &[PitchTrailPoint],
-) -> (Option<f64>, Option<f64>, Option<f64>, Option<f64>, Vec<String>) {
-    if pitch_trail.is_empty() || target_notes.is_empty() {
-        return (None, None, None, None, Vec::new());
+    let mean = intervals.iter().sum::<f64>() / intervals.len() as f64;
+    let variance =
+        intervals.iter().map(|i| (i - mean).powi(2)).sum::<f64>() / intervals.len() as f64;
+    ArticulationReport {
+        notes_per_second: if mean > 0.0 { 1.0 / mean } else { 0.0 },
+        mean_interval_seconds: mean,
+        interval_std_dev_seconds: variance.sqrt(),
     }
+}
 
-    let mut stability_values: Vec<f64> = Vec::new();
-    let mut attack_times: Vec<f64> = Vec::new();
-    let mut sustain_drifts: Vec<f64> = Vec::new();
-    let mut technique_feedback = Vec::new();
+/// Evenness of a run that should be equally spaced (a scale in eighths, a
+/// tonguing drill): the coefficient of variation of the inter-onset
+/// intervals, 0 for metronomic and growing as the run gets ragged. Tempo
+/// doesn't matter, so the run can be checked without a score. Fewer than
+/// three onsets, or all at the same beat, give 0.
+pub fn evenness(played: &[PlayedNote]) -> f64 {
+    let mut onsets: Vec<f64> = played.iter().map(|p| p.onset_beat).collect();
+    onsets.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
 
-    for target in target_notes {
-        let note_end = target.start_beat + target.duration_beats;
-        let trail_points: Vec<&PitchTrailPoint> = pitch_trail
-            .iter()
-            .filter(|p| p.beat >= target.start_beat && p.beat < note_end)
-            .collect();
+    let intervals: Vec<f64> = onsets.windows(2).map(|w| w[1] - w[0]).collect();
+    if intervals.len() < 2 {
+        return 0.0;
+    }
+    let mean = intervals.iter().sum::<f64>() / intervals.len() as f64;
+    if mean <= 0.0 {
+        return 0.0;
+    }
+    let variance =
+        intervals.iter().map(|i| (i - mean).powi(2)).sum::<f64>() / intervals.len() as f64;
+    variance.sqrt() / mean
+}
+
+/// Distribution of detector confidences over a take: counts per `bins`
+/// equal-width buckets spanning 0..1. Out-of-range values are clamped, so
+/// a confidence of exactly 1.0 lands in the last bucket.
+pub fn confidence_histogram(played: &[PlayedNote], bins: usize) -> Vec<usize> {
+    let mut counts = vec![0; bins];
+    if bins == 0 {
+        return counts;
+    }
+    for note in played {
+        let idx = (note.confidence.clamp(0.0, 1.0) * bins as f64) as usize;
+        counts[idx.min(bins - 1)] += 1;
+    }
+    counts
+}
+
+/// Played notes within this many beats of a target are compared against it
+/// when looking for a constant transposition.
+const OFFSET_MATCH_WINDOW_BEATS: f64 = 0.5;
+
+/// Semitone offset the player applied to the whole passage (e.g. -2 when
+/// everything is a whole step low), found by pairing each target with the
+/// nearest unused played onset. Returns the offset only when it is nonzero
+/// and shared by more than half of the target notes.
+pub fn detect_constant_offset(score: &Score, played: &[PlayedNote]) -> Option<i32> {
+    let targets: Vec<&NoteEvent> = score.notes.iter().filter(|n| n.is_played()).collect();
+    let mut used = vec![false; played.len()];
+    let mut counts: BTreeMap<i32, usize> = BTreeMap::new();
+    for target in &targets {
+        let nearest = played
+            .iter()
+            .enumerate()
+            .filter(|&(i, p)| {
+                !used[i] && (p.onset_beat - target.start_beat).abs() <= OFFSET_MATCH_WINDOW_BEATS
+            })
+            .min_by(|(_, a), (_, b)| {
+                let da = (a.onset_beat - target.start_beat).abs();
+                let db = (b.onset_beat - target.start_beat).abs();
+                da.partial_cmp(&db).unwrap_or(Ordering::Equal)
+            });
+        if let Some((i, p)) = nearest {
+            used[i] = true;
+            *counts.entry((p.midi_float - target.midi as f64).round() as i32).or_default() += 1;
+        }
+    }
+
+    let (offset, count) = counts.into_iter().max_by_key(|&(_, count)| count)?;
+    (offset != 0 && count * 2 > targets.len()).then_some(offset)
+}
+
+/// Longest run of consecutive "correct" notes, e.g. for "you nailed 12
+/// notes in a row".
+pub fn longest_correct_streak(note_results: &[NoteResult]) -> u32 {
+    let mut longest = 0;
+    let mut current = 0;
+    for result in note_results {
+        if result.status == "correct" {
+            current += 1;
+            longest = longest.max(current);
+        } else {
+            current = 0;
+        }
+    }
+    longest
+}
+
+/// Intonation spread across repeats of the same pitch, in cents: the
+/// standard deviation of pitch errors for each target MIDI note played at
+/// least twice, averaged over those notes. Lower is more consistent; 0.0
+/// when no pitch was repeated.
+pub fn pitch_consistency(note_results: &[NoteResult]) -> f64 {
+    let mut by_pitch: BTreeMap<i32, Vec<f64>> = BTreeMap::new();
+    for result in note_results {
+        if let Some(cents) = result.pitch_error_cents {
+            by_pitch.entry(result.target_midi).or_default().push(cents);
+        }
+    }
+
+    let spreads: Vec<f64> = by_pitch
+        .values()
+        .filter(|errors| errors.len() >= 2)
+        .map(|errors| {
+            let mean = errors.iter().sum::<f64>() / errors.len() as f64;
+            let variance =
+                errors.iter().map(|e| (e - mean).powi(2)).sum::<f64>() / errors.len() as f64;
+            variance.sqrt()
+        })
+        .collect();
+    if spreads.is_empty() {
+        return 0.0;
+    }
+    spreads.iter().sum::<f64>() / spreads.len() as f64
+}
+
+/// Readiness to move on after a take, 0-100: `overall_score` scaled by the
+/// score's `estimate_difficulty`, from half credit on the easiest material to
+/// full credit on the hardest. A clean take of an easy piece leaves more
+/// headroom than the same result on a hard one.
+pub fn readiness(score: &Score, analysis: &PerformanceAnalysis) -> f64 {
+    analysis.overall_score * (0.5 + 0.5 * estimate_difficulty(score))
+}
+
+/// One-paragraph summary of a take for compact displays: the score line
+/// from `feedback` followed by the single biggest problem, taken in order
+/// from the worst pitch interval, the worst timing interval, missed notes,
+/// then the overall pitch and timing tendencies.
+#[cfg(feature = "std")]
+pub fn summarize_feedback(analysis: &PerformanceAnalysis) -> String {
+    let Some(headline) = analysis.feedback.first() else {
+        return String::new();
+    };
+    if analysis.total_notes == 0 {
+        return headline.clone();
+    }
+
+    let problem = if let Some(p) = analysis.problem_intervals.first() {
+        Some(format!(
+            "you {} going {} from {} to {} (avg {:+.0} cents)",
+            if p.avg_error_cents > 0.0 { "overshoot" } else { "undershoot" },
+            p.direction,
+            p.from_note,
+            p.to_note,
+            p.avg_error_cents
+        ))
+    } else if let Some(p) = analysis.timing_problem_intervals.first() {
+        Some(format!(
+            "you come in {} after the jump from {} to {}",
+            if p.avg_timing_error_beats > 0.0 { "late" } else { "early" },
+            p.from_note,
+            p.to_note
+        ))
+    } else if analysis.notes_missed > 0 {
+        Some(format!(
+            "{} missed note{}",
+            analysis.notes_missed,
+            if analysis.notes_missed == 1 { "" } else { "s" }
+        ))
+    } else if analysis.pitch_tendency != "accurate" {
+        Some(format!("your pitch runs {}", analysis.pitch_tendency))
+    } else if analysis.timing_tendency != "on_time" {
+        Some(format!("you tend to come in {}", analysis.timing_tendency))
+    } else {
+        None
+    };
+
+    match problem {
+        Some(problem) => format!("{} Biggest issue: {}.", headline, problem),
+        None => headline.clone(),
+    }
+}
+
+/// Average `overall_score` per exercise label, e.g. to show a student which
+/// exercise types they are strongest on. Labels keep first-seen order.
+pub fn profile_by_exercise(results: &[(String, PerformanceAnalysis)]) -> Vec<(String, f64)> {
+    let mut totals: Vec<(String, f64, u32)> = Vec::new();
+    for (label, analysis) in results {
+        match totals.iter_mut().find(|(l, _, _)| l == label) {
+            Some(entry) => {
+                entry.1 += analysis.overall_score;
+                entry.2 += 1;
+            }
+            None => totals.push((label.clone(), analysis.overall_score, 1)),
+        }
+    }
+    totals
+        .into_iter()
+        .map(|(label, sum, count)| (label, sum / count as f64))
+        .collect()
+}
+
+/// Staccato notes sounding for at least this fraction of their written
+/// length count as held.
+const STACCATO_MAX_FRACTION: f64 = 0.75;
+/// Accented notes should center within this fraction of their trail points.
+const ACCENT_MAX_ATTACK_RATIO: f64 = 0.25;
+
+/// Check that staccato notes were actually short and accented notes had a
+/// crisp attack, using the pitch trail as the record of when sound was present.
+fn analyze_articulation(
+    target_notes: &[&NoteEvent],
+    note_results: &[NoteResult],
+    pitch_trail: &[PitchTrailPoint],
+) -> Vec<String> {
+    let mut held_staccato = 0;
+    let mut soft_accents = 0;
+
+    for (target, result) in target_notes.iter().zip(note_results) {
+        if target.articulations.is_empty() || result.status == "missed" {
+            continue;
+        }
+        let note_end = target.start_beat + target.duration_beats;
+        let trail_points: Vec<&PitchTrailPoint> = pitch_trail
+            .iter()
+            .filter(|p| p.beat >= target.start_beat && p.beat < note_end)
+            .collect();
+        if trail_points.len() < 2 {
+            continue;
+        }
+
+        let has = |mark: &str| target.articulations.iter().any(|a| a == mark);
+
+        if has("staccato") || has("staccatissimo") {
+            let sounding = trail_points[trail_points.len() - 1].beat - trail_points[0].beat;
+            if sounding >= target.duration_beats * STACCATO_MAX_FRACTION {
+                held_staccato += 1;
+            }
+        }
+
+        if has("accent") || has("strong-accent") {
+            let attack_count = attack_point_count(&trail_points, target.target_pitch());
+            if attack_count as f64 / trail_points.len() as f64 > ACCENT_MAX_ATTACK_RATIO {
+                soft_accents += 1;
+            }
+        }
+    }
+
+    let mut feedback: Vec<String> = Vec::new();
+    if held_staccato > 0 {
+        feedback!(
+            feedback,
+            "{} staccato note{} held too long. Keep them short and light.",
+            held_staccato,
+            if held_staccato == 1 { " was" } else { "s were" }
+        );
+    }
+    if soft_accents > 0 {
+        feedback!(
+            feedback,
+            "{} accented note{} lacked a crisp attack. Use a firmer tongue stroke.",
+            soft_accents,
+            if soft_accents == 1 { "" } else { "s" }
+        );
+    }
+    feedback
+}
+
+/// Where played notes settle relative to their targets: the signed mean
+/// cents of each note's steady state (its trail points after the attack),
+/// averaged over notes. Distinct from stability (wobble around that point)
+/// and attack (time to get there). `None` if no played note settles.
+fn centering(
+    target_notes: &[&NoteEvent],
+    note_results: &[NoteResult],
+    trail: &[PitchTrailPoint],
+    options: &AnalysisOptions,
+) -> Option<f64> {
+    let mut offsets: Vec<f64> = Vec::new();
+    for (target, result) in target_notes.iter().zip(note_results) {
+        if result.status == "missed" {
+            continue;
+        }
+        let points = note_points(target, trail);
+        let target_midi = graded_pitch(target, options);
+        let steady = &points[attack_point_count(&points, target_midi)..];
+        if steady.is_empty() {
+            continue;
+        }
+        let cents: f64 = steady.iter().map(|p| (p.midi_float - target_midi) * 100.0).sum();
+        offsets.push(cents / steady.len() as f64);
+    }
+    if offsets.is_empty() {
+        return None;
+    }
+    Some(offsets.iter().sum::<f64>() / offsets.len() as f64)
+}
+
+/// Attack time of each played note in milliseconds at `tempo`: its attack
+/// trail points (as counted for `attack_quality`, against the pitch
+/// `options` grade by) times the trail's mean point spacing. `target_notes`
+/// and `note_results` run in parallel; missed notes and notes with fewer
+/// than three trail points are skipped.
+pub fn attack_times_ms(
+    target_notes: &[&NoteEvent],
+    note_results: &[NoteResult],
+    trail: &[PitchTrailPoint],
+    tempo: f64,
+    options: &AnalysisOptions,
+) -> Vec<f64> {
+    let (Some(first), Some(last)) = (trail.first(), trail.last()) else {
+        return Vec::new();
+    };
+    if trail.len() < 2 || tempo <= 0.0 {
+        return Vec::new();
+    }
+    let spacing_ms = (last.beat - first.beat) / (trail.len() - 1) as f64 * 60_000.0 / tempo;
+
+    let mut times = Vec::new();
+    for (target, result) in target_notes.iter().zip(note_results) {
+        if result.status == "missed" {
+            continue;
+        }
+        let points = note_points(target, trail);
+        if points.len() < 3 {
+            continue;
+        }
+        let attack = attack_point_count(&points, graded_pitch(target, options));
+        times.push(attack as f64 * spacing_ms);
+    }
+    times
+}
+
+/// A matched note whose trail confidence never reaches this is airy: it
+/// sounds, but without a supported, focused tone.
+const AIRY_MAX_CONFIDENCE: f64 = 0.8;
+
+/// Matched notes whose trail points (at least two) all have detector
+/// confidence below `AIRY_MAX_CONFIDENCE`.
+fn count_airy_notes(
+    target_notes: &[&NoteEvent],
+    note_results: &[NoteResult],
+    pitch_trail: &[PitchTrailPoint],
+    confidences: &[f64],
+) -> u32 {
+    let len = pitch_trail.len().min(confidences.len());
+    let mut airy = 0;
+    for (target, result) in target_notes.iter().zip(note_results) {
+        if result.status == "missed" {
+            continue;
+        }
+        let note_end = target.start_beat + target.duration_beats;
+        let note_confidences: Vec<f64> = (0..len)
+            .filter(|&i| pitch_trail[i].beat >= target.start_beat && pitch_trail[i].beat < note_end)
+            .map(|i| confidences[i])
+            .collect();
+        if note_confidences.len() >= 2 && note_confidences.iter().all(|&c| c < AIRY_MAX_CONFIDENCE) {
+            airy += 1;
+        }
+    }
+    airy
+}
+
+/// Written notes from here up (F5, the top line of the staff) count as the
+/// high register when checking for forcing.
+const FORCING_MIN_MIDI: i32 = 77;
+/// A high note this many cents sharp or more is a forcing candidate...
+const FORCING_SHARP_CENTS: f64 = 15.0;
+/// ...and is forced if its trail's standard deviation also reaches this.
+const FORCING_UNSTABLE_CENTS: f64 = 15.0;
+
+/// Matched high-register notes (from `FORCING_MIN_MIDI`) that are both
+/// sharp and unstable, the signature of pinching or too much pressure.
+/// Notes need at least three trail points.
+fn count_forced_notes(
+    target_notes: &[&NoteEvent],
+    note_results: &[NoteResult],
+    pitch_trail: &[PitchTrailPoint],
+    options: &AnalysisOptions,
+) -> u32 {
+    let mut forced = 0;
+    for (target, result) in target_notes.iter().zip(note_results) {
+        let Some(error) = result.pitch_error_cents else {
+            continue;
+        };
+        if target.midi < FORCING_MIN_MIDI || error < FORCING_SHARP_CENTS {
+            continue;
+        }
+        let target_midi = graded_pitch(target, options);
+        let cents: Vec<f64> = note_points(target, pitch_trail)
+            .iter()
+            .map(|p| (p.midi_float - target_midi) * 100.0)
+            .collect();
+        if cents.len() < 3 {
+            continue;
+        }
+        let mean = cents.iter().sum::<f64>() / cents.len() as f64;
+        let variance = cents.iter().map(|c| (c - mean).powi(2)).sum::<f64>() / cents.len() as f64;
+        if variance.sqrt() >= FORCING_UNSTABLE_CENTS {
+            forced += 1;
+        }
+    }
+    forced
+}
+
+/// Resolution, in beats, at which sounding time is checked for silence.
+const SILENCE_GRID_BEATS: f64 = 0.25;
+
+/// Fraction of the grid cells under `targets` in which nothing was heard.
+/// With a pitch trail a cell is sounded if it holds a trail point. Without
+/// one, each played note is taken to last until the next onset, but no
+/// longer than the target it starts in (or one cell if it starts in a rest).
+fn silence_ratio(
+    targets: &[&NoteEvent],
+    played: &[PlayedNote],
+    trail: Option<&[PitchTrailPoint]>,
+) -> f64 {
+    let cell_of = |beat: f64| (beat / SILENCE_GRID_BEATS).floor() as i64;
+    // Cells are shared by chord members, so collect them as a set
+    let expected: BTreeSet<i64> = targets
+        .iter()
+        .flat_map(|t| cell_of(t.start_beat)..cell_of(t.start_beat + t.duration_beats))
+        .collect();
+    if expected.is_empty() {
+        return 0.0;
+    }
+
+    let sounded: BTreeSet<i64> = match trail {
+        Some(trail) => trail.iter().map(|p| cell_of(p.beat)).collect(),
+        None => {
+            let mut onsets: Vec<f64> = played.iter().map(|p| p.onset_beat).collect();
+            onsets.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+            let mut cells = BTreeSet::new();
+            for (i, &onset) in onsets.iter().enumerate() {
+                let target_end = targets
+                    .iter()
+                    .filter(|t| t.start_beat <= onset && onset < t.start_beat + t.duration_beats)
+                    .map(|t| t.start_beat + t.duration_beats)
+                    .fold(onset, f64::max);
+                let next = onsets.get(i + 1).copied().unwrap_or(f64::INFINITY);
+                let end = target_end.min(next);
+                cells.extend(cell_of(onset)..cell_of(end).max(cell_of(onset) + 1));
+            }
+            cells
+        }
+    };
+
+    let silent = expected.difference(&sounded).count();
+    silent as f64 / expected.len() as f64
+}
+
+/// The rest in `score` sounding at `beat`, if any.
+fn rest_at(score: &Score, beat: f64) -> Option<&NoteEvent> {
+    score
+        .notes
+        .iter()
+        .find(|n| n.is_rest && beat >= n.start_beat && beat < n.start_beat + n.duration_beats)
+}
+
+/// Number of played notes not matched to any target whose onset falls
+/// inside a notated rest.
+fn count_rest_violations(score: &Score, played_notes: &[PlayedNote], used_played: &[bool]) -> u32 {
+    played_notes
+        .iter()
+        .zip(used_played)
+        .filter(|(played, &used)| !used && rest_at(score, played.onset_beat).is_some())
+        .count() as u32
+}
+
+/// Every played note that starts during a rest, with the rest it broke, in
+/// played order. Unlike the analyzer's `rest_penalty`, this does no note
+/// matching, so an entrance anticipated into a rest is listed too.
+pub fn rest_violations(score: &Score, played: &[PlayedNote]) -> Vec<RestViolation> {
+    played
+        .iter()
+        .filter_map(|p| {
+            let rest = rest_at(score, p.onset_beat)?;
+            Some(RestViolation {
+                rest_start_beat: rest.start_beat,
+                rest_end_beat: rest.start_beat + rest.duration_beats,
+                played: p.clone(),
+            })
+        })
+        .collect()
+}
+
+/// (pitch_stability, attack_quality, breath_support, endurance_delta, technique_feedback)
+type TechniqueMetrics = (Option<f64>, Option<f64>, Option<f64>, Option<f64>, Vec<String>);
+
+fn analyze_technique(
+    target_notes: &[&NoteEvent],
+    note_results: &[NoteResult],
+    pitch_trail: &[PitchTrailPoint],
+    options: &AnalysisOptions,
+) -> TechniqueMetrics {
+    if pitch_trail.is_empty() || target_notes.is_empty() {
+        return (None, None, None, None, Vec::new());
+    }
+
+    let mut stability_values: Vec<f64> = Vec::new();
+    let mut attack_times: Vec<f64> = Vec::new();
+    let mut sustain_drifts: Vec<f64> = Vec::new();
+    let mut technique_feedback = Vec::new();
+
+    for target in target_notes {
+        let trail_points = note_points(target, pitch_trail);
 
         if trail_points.len() < 3 {
             continue;
         }
 
-        let target_midi = target.midi as f64;
+        let target_midi = graded_pitch(target, options);
 
         // Pitch stability: std dev of cents within held notes
         let cents: Vec<f64> = trail_points
@@ -332,13 +1513,7 @@ fn analyze_technique(
         stability_values.push(variance.sqrt());
 
         // Attack quality: how many trail points until within 20 cents of target
-        let mut attack_count = 0;
-        for c in &cents {
-            if c.abs() <= 20.0 {
-                break;
-            }
-            attack_count += 1;
-        }
+        let attack_count = attack_point_count(&trail_points, target_midi);
         let attack_ratio = attack_count as f64 / trail_points.len() as f64;
         attack_times.push(attack_ratio);
 
@@ -403,30 +1578,33 @@ fn analyze_technique(
     // Generate technique feedback
     if let Some(stability) = pitch_stability {
         if stability > 15.0 {
-            technique_feedback.push(
-                "Your pitch wobbles on sustained notes. Focus on steady airflow.".to_string(),
+            feedback!(
+                technique_feedback,
+                "Your pitch wobbles on sustained notes. Focus on steady airflow."
             );
         }
     }
     if let Some(attack) = attack_quality {
         if attack < 0.7 {
-            technique_feedback.push(
-                "Your note attacks are slow to center. Try a firmer tongue stroke.".to_string(),
+            feedback!(
+                technique_feedback,
+                "Your note attacks are slow to center. Try a firmer tongue stroke."
             );
         }
     }
     if let Some(breath) = breath_support {
         if breath < 0.7 {
-            technique_feedback.push(
-                "Your pitch drops through long notes. Practice deep breathing.".to_string(),
+            feedback!(
+                technique_feedback,
+                "Your pitch drops through long notes. Practice deep breathing."
             );
         }
     }
     if let Some(delta) = endurance_delta {
         if delta > 15.0 {
-            technique_feedback.push(
+            feedback!(
+                technique_feedback,
                 "Your accuracy drops later in the piece. Build endurance with long tones."
-                    .to_string(),
             );
         }
     }
@@ -440,171 +1618,1260 @@ fn analyze_technique(
     )
 }
 
-fn analyze_intervals(
-    _target_notes: &[&NoteEvent],
-    results: &[NoteResult],
-    tolerance_cents: f64,
-) -> Vec<IntervalProblem> {
-    use std::collections::HashMap;
+/// Per-occurrence timing error (beats) for an entry to count towards a timing
+/// problem interval; the average must also exceed the feedback threshold.
+const INTERVAL_TIMING_ERROR_BEATS: f64 = 0.1;
+const INTERVAL_TIMING_AVG_BEATS: f64 = 0.15;
+
+/// Pitch and timing problem intervals: transitions whose arrival note is
+/// repeatedly out of tune, or repeatedly entered late or early.
+fn analyze_intervals(
+    _target_notes: &[&NoteEvent],
+    results: &[NoteResult],
+    tolerance_cents: f64,
+) -> (Vec<IntervalProblem>, Vec<IntervalProblem>) {
+    // Track (cents, beats) errors per interval (from_midi, to_midi)
+    let mut pitch_errors: BTreeMap<(i32, i32), Vec<(f64, f64)>> = BTreeMap::new();
+    let mut timing_errors: BTreeMap<(i32, i32), Vec<(f64, f64)>> = BTreeMap::new();
+
+    for i in 1..results.len() {
+        let prev = &results[i - 1];
+        let curr = &results[i];
+
+        // Only analyze intervals where both notes were played; pitch-only
+        // results carry no timing, so they never flag timing problems
+        if let (Some(_prev_cents), Some(curr_cents)) =
+            (prev.pitch_error_cents, curr.pitch_error_cents)
+        {
+            let curr_beats = curr.timing_error_beats.unwrap_or(0.0);
+            let key = (prev.target_midi, curr.target_midi);
+            if curr_cents.abs() > tolerance_cents * 0.5 {
+                pitch_errors.entry(key).or_default().push((curr_cents, curr_beats));
+            }
+            if curr_beats.abs() > INTERVAL_TIMING_ERROR_BEATS {
+                timing_errors.entry(key).or_default().push((curr_cents, curr_beats));
+            }
+        }
+    }
+
+    (
+        worst_intervals(&pitch_errors, |p| p.avg_error_cents, 20.0),
+        worst_intervals(&timing_errors, |p| p.avg_timing_error_beats, INTERVAL_TIMING_AVG_BEATS),
+    )
+}
+
+/// Top three intervals whose `metric` averages beyond `threshold` over at
+/// least two occurrences, most severe first.
+fn worst_intervals(
+    interval_errors: &BTreeMap<(i32, i32), Vec<(f64, f64)>>,
+    metric: fn(&IntervalProblem) -> f64,
+    threshold: f64,
+) -> Vec<IntervalProblem> {
+    let mut problems: Vec<IntervalProblem> = Vec::new();
+    for ((from_midi, to_midi), errors) in interval_errors {
+        if errors.len() < 2 {
+            continue; // Need at least 2 occurrences to call it a pattern
+        }
+        let n = errors.len() as f64;
+        let direction = if to_midi > from_midi { "up" } else { "down" };
+        let problem = IntervalProblem {
+            from_note: midi_to_name(*from_midi),
+            to_note: midi_to_name(*to_midi),
+            direction: direction.to_string(),
+            avg_error_cents: errors.iter().map(|e| e.0).sum::<f64>() / n,
+            avg_timing_error_beats: errors.iter().map(|e| e.1).sum::<f64>() / n,
+            count: errors.len() as u32,
+        };
+        if metric(&problem).abs() > threshold {
+            problems.push(problem);
+        }
+    }
+
+    // Sort by severity
+    problems.sort_by(|a, b| {
+        metric(b)
+            .abs()
+            .partial_cmp(&metric(a).abs())
+            .unwrap_or(Ordering::Equal)
+    });
+    problems.truncate(3); // Top 3 problem intervals
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_score(notes: Vec<(f64, f64, i32)>) -> Score {
+        Score {
+            tempo: 120.0,
+            notes: notes
+                .into_iter()
+                .map(|(beat, dur, midi)| NoteEvent {
+                    start_beat: beat,
+                    duration_beats: dur,
+                    midi,
+                    is_rest: false,
+                    measure_number: 1,
+                    note_type: "quarter".to_string(),
+                    microtone_cents: 0.0,
+                    articulations: Vec::new(),
+                    beam_group: None,
+                    dynamics: None,
+                    velocity: None,
+                    ornaments: Vec::new(),
+                    accidental: None,
+                    glissando_to: false,
+                    accompaniment: false,
+                })
+                .collect(),
+            measures: vec![],
+            key_fifths: 0,
+            transpose: None,
+            title: None,
+            total_beats: 4.0,
+            wedges: Vec::new(),
+        }
+    }
+
+    /// Analyze an n-note scale played in tune with a steady trail, plus the
+    /// same score with nothing played.
+    fn analyze_small(n: usize) -> (PerformanceAnalysis, PerformanceAnalysis) {
+        let notes: Vec<(f64, f64, i32)> = (0..n).map(|i| (i as f64, 1.0, 60 + 2 * i as i32)).collect();
+        let score = make_score(notes.clone());
+        let played: Vec<PlayedNote> = notes
+            .iter()
+            .map(|&(beat, _, midi)| PlayedNote {
+                onset_beat: beat,
+                midi_float: midi as f64,
+                midi_rounded: midi,
+                confidence: 0.9,
+            })
+            .collect();
+        let trail: Vec<PitchTrailPoint> = notes
+            .iter()
+            .flat_map(|&(beat, _, midi)| {
+                (0..5).map(move |i| PitchTrailPoint { beat: beat + i as f64 * 0.2, midi_float: midi as f64 })
+            })
+            .collect();
+        (
+            analyze_performance_with_trail(&score, &played, 50.0, 0.3, Some(&trail)),
+            analyze_performance_with_trail(&score, &[], 50.0, 0.3, Some(&[])),
+        )
+    }
+
+    fn assert_small_n_sensible(n: usize) {
+        let (played, silent) = analyze_small(n);
+        assert_eq!(played.total_notes, n as u32);
+        assert_eq!(played.notes_correct, n as u32);
+        assert!(played.problem_intervals.is_empty());
+        assert_eq!(played.endurance_delta, None);
+        assert!(played.overall_score.is_finite() && played.overall_score > 90.0);
+        assert!(played.pitch_stability.is_some_and(|s| s.is_finite()));
+
+        assert_eq!(silent.notes_missed, n as u32);
+        assert!(silent.problem_intervals.is_empty());
+        assert_eq!(silent.endurance_delta, None);
+        assert_eq!(silent.pitch_stability, None);
+        assert_eq!(silent.avg_pitch_error_cents, 0.0);
+        assert_eq!(silent.overall_score, 0.0);
+    }
+
+    #[test]
+    fn test_single_note_score() {
+        assert_small_n_sensible(1);
+    }
+
+    #[test]
+    fn test_two_note_score() {
+        assert_small_n_sensible(2);
+    }
+
+    #[test]
+    fn test_three_note_score() {
+        assert_small_n_sensible(3);
+    }
+
+    #[test]
+    fn test_perfect_performance() {
+        let score = make_score(vec![(0.0, 1.0, 60), (1.0, 1.0, 62), (2.0, 1.0, 64)]);
+        let played = vec![
+            PlayedNote {
+                onset_beat: 0.0,
+                midi_float: 60.0,
+                midi_rounded: 60,
+                confidence: 0.9,
+            },
+            PlayedNote {
+                onset_beat: 1.0,
+                midi_float: 62.0,
+                midi_rounded: 62,
+                confidence: 0.9,
+            },
+            PlayedNote {
+                onset_beat: 2.0,
+                midi_float: 64.0,
+                midi_rounded: 64,
+                confidence: 0.9,
+            },
+        ];
+
+        let result = analyze_performance(&score, &played, 50.0, 0.25);
+        assert_eq!(result.total_notes, 3);
+        assert_eq!(result.notes_correct, 3);
+        assert_eq!(result.notes_missed, 0);
+        assert_eq!(result.notes_wrong_pitch, 0);
+        assert!(result.overall_score > 70.0);
+        assert_eq!(result.pitch_tendency, "accurate");
+        assert_eq!(result.timing_tendency, "on_time");
+    }
+
+    #[test]
+    fn test_missed_notes() {
+        let score = make_score(vec![(0.0, 1.0, 60), (1.0, 1.0, 62), (2.0, 1.0, 64)]);
+        let played = vec![PlayedNote {
+            onset_beat: 0.0,
+            midi_float: 60.0,
+            midi_rounded: 60,
+            confidence: 0.9,
+        }];
+
+        let result = analyze_performance(&score, &played, 50.0, 0.25);
+        assert_eq!(result.notes_correct, 1);
+        assert_eq!(result.notes_missed, 2);
+    }
+
+    #[test]
+    fn test_sharp_tendency() {
+        let score = make_score(vec![(0.0, 1.0, 60), (1.0, 1.0, 62)]);
+        let played = vec![
+            PlayedNote {
+                onset_beat: 0.0,
+                midi_float: 60.2,
+                midi_rounded: 60,
+                confidence: 0.9,
+            },
+            PlayedNote {
+                onset_beat: 1.0,
+                midi_float: 62.3,
+                midi_rounded: 62,
+                confidence: 0.9,
+            },
+        ];
+
+        let result = analyze_performance(&score, &played, 50.0, 0.25);
+        assert_eq!(result.notes_correct, 2);
+        assert_eq!(result.pitch_tendency, "sharp");
+    }
+
+    #[test]
+    fn test_wrong_pitch() {
+        let score = make_score(vec![(0.0, 1.0, 60)]);
+        let played = vec![PlayedNote {
+            onset_beat: 0.0,
+            midi_float: 62.0,            // 200 cents off
+            midi_rounded: 62,
+            confidence: 0.9,
+        }];
+
+        let result = analyze_performance(&score, &played, 50.0, 0.25);
+        assert_eq!(result.notes_wrong_pitch, 1);
+        assert_eq!(result.notes_correct, 0);
+    }
+
+    #[test]
+    fn test_snap_microtones() {
+        // C quarter-sharp: written as C#4 minus 50 cents
+        let mut score = make_score(vec![(0.0, 1.0, 61)]);
+        score.notes[0].microtone_cents = -50.0;
+        let played = vec![PlayedNote {
+            onset_beat: 0.0,
+            midi_float: 61.0,
+            midi_rounded: 61,
+            confidence: 0.9,
+        }];
+
+        let strict = analyze_performance(&score, &played, 40.0, 0.25);
+        assert_eq!(strict.notes_wrong_pitch, 1);
+        assert_eq!(strict.note_results[0].pitch_error_cents, Some(50.0));
+
+        let options = AnalysisOptions {
+            snap_microtones: true,
+            ..Default::default()
+        };
+        let snapped =
+            analyze_performance_with_options(&score, &played, 40.0, 0.25, None, &options);
+        // Snapping decides the grade; the error is still from the quarter-tone
+        assert_eq!(snapped.notes_correct, 1);
+        assert_eq!(snapped.note_results[0].pitch_error_cents, Some(50.0));
+    }
+
+    fn four_four(number: u32, start_beat: f64) -> MeasureInfo {
+        MeasureInfo {
+            number,
+            start_beat,
+            duration_beats: 4.0,
+            time_sig_num: 4,
+            time_sig_den: 4,
+            clef: Clef::treble(),
+            unmetered: false,
+        }
+    }
+
+    #[test]
+    fn test_beat_strength() {
+        let m = four_four(2, 4.0);
+        assert_eq!(beat_strength(4.0, &m), 1.0);
+        assert_eq!(beat_strength(6.0, &m), 0.75);
+        assert_eq!(beat_strength(5.0, &m), 0.5);
+        assert_eq!(beat_strength(6.5, &m), 0.25);
+
+        let six_eight = MeasureInfo {
+            number: 1,
+            start_beat: 0.0,
+            duration_beats: 3.0,
+            time_sig_num: 6,
+            time_sig_den: 8,
+            clef: Clef::treble(),
+            unmetered: false,
+        };
+        assert_eq!(beat_strength(0.0, &six_eight), 1.0);
+        assert_eq!(beat_strength(1.5, &six_eight), 0.75);
+        assert_eq!(beat_strength(0.5, &six_eight), 0.5);
+    }
+
+    #[test]
+    fn test_timing_weighted_by_beat_strength() {
+        // Same 0.2-beat error: late on beat 1, early on beat 3.5
+        let mut score = make_score(vec![(0.0, 1.0, 60), (2.5, 0.5, 62)]);
+        score.measures = vec![four_four(1, 0.0)];
+        let played = vec![
+            PlayedNote { onset_beat: 0.2, midi_float: 60.0, midi_rounded: 60, confidence: 0.9 },
+            PlayedNote { onset_beat: 2.3, midi_float: 62.0, midi_rounded: 62, confidence: 0.9 },
+        ];
+
+        let plain = analyze_performance(&score, &played, 50.0, 0.3);
+        assert!(plain.avg_timing_error_beats.abs() < 1e-9);
+
+        let options = AnalysisOptions {
+            weight_timing_by_beat: true,
+            ..Default::default()
+        };
+        let weighted =
+            analyze_performance_with_options(&score, &played, 50.0, 0.3, None, &options);
+        // (0.2 * 1.0 - 0.2 * 0.25) / 1.25 = 0.12
+        assert!((weighted.avg_timing_error_beats - 0.12).abs() < 1e-9);
+        assert_eq!(weighted.timing_tendency, "late");
+    }
+
+    #[test]
+    fn test_rest_penalty() {
+        let mut score = make_score(vec![(0.0, 1.0, 60), (1.0, 2.0, -1), (3.0, 1.0, 62)]);
+        score.notes[1].is_rest = true;
+        let mut played = vec![
+            PlayedNote { onset_beat: 0.0, midi_float: 60.0, midi_rounded: 60, confidence: 0.9 },
+            PlayedNote { onset_beat: 3.0, midi_float: 62.0, midi_rounded: 62, confidence: 0.9 },
+        ];
+        let options = AnalysisOptions {
+            rest_penalty: 5.0,
+            ..Default::default()
+        };
+
+        let rested = analyze_performance_with_options(&score, &played, 50.0, 0.25, None, &options);
+
+        played.insert(
+            1,
+            PlayedNote { onset_beat: 1.8, midi_float: 64.0, midi_rounded: 64, confidence: 0.9 },
+        );
+        let noodled =
+            analyze_performance_with_options(&score, &played, 50.0, 0.25, None, &options);
+        assert_eq!(noodled.notes_correct, 2);
+        assert!((rested.overall_score - noodled.overall_score - 5.0).abs() < 1e-9);
+
+        // No penalty by default
+        let default = analyze_performance(&score, &played, 50.0, 0.25);
+        assert_eq!(default.overall_score, rested.overall_score);
+    }
+
+    #[test]
+    fn test_rest_violations() {
+        let mut score = make_score(vec![(0.0, 1.0, 60), (1.0, 2.0, -1), (3.0, 1.0, 62)]);
+        score.notes[1].is_rest = true;
+        let played = vec![
+            PlayedNote { onset_beat: 0.0, midi_float: 60.0, midi_rounded: 60, confidence: 0.9 },
+            PlayedNote { onset_beat: 1.8, midi_float: 64.0, midi_rounded: 64, confidence: 0.9 },
+            PlayedNote { onset_beat: 3.0, midi_float: 62.0, midi_rounded: 62, confidence: 0.9 },
+        ];
+
+        let violations = rest_violations(&score, &played);
+        assert_eq!(violations.len(), 1);
+        let v = &violations[0];
+        assert_eq!((v.rest_start_beat, v.rest_end_beat), (1.0, 3.0));
+        assert_eq!(v.played.midi_rounded, 64);
+
+        assert!(rest_violations(&score, &[played[0].clone(), played[2].clone()]).is_empty());
+    }
+
+    #[test]
+    fn test_late_after_leap() {
+        let score = make_score(vec![
+            (0.0, 1.0, 60),
+            (1.0, 1.0, 72),
+            (2.0, 1.0, 60),
+            (3.0, 1.0, 72),
+            (4.0, 1.0, 60),
+            (5.0, 1.0, 72),
+        ]);
+        // In tune throughout, but every C5 after the octave leap is late
+        let played: Vec<PlayedNote> = score
+            .notes
+            .iter()
+            .map(|n| PlayedNote {
+                onset_beat: n.start_beat + if n.midi == 72 { 0.25 } else { 0.0 },
+                midi_float: n.midi as f64,
+                midi_rounded: n.midi,
+                confidence: 0.9,
+            })
+            .collect();
+
+        let analysis = analyze_performance(&score, &played, 50.0, 0.3);
+        assert!(analysis.problem_intervals.is_empty());
+        assert_eq!(analysis.timing_problem_intervals.len(), 1);
+        let problem = &analysis.timing_problem_intervals[0];
+        assert_eq!((problem.from_note.as_str(), problem.to_note.as_str()), ("C4", "C5"));
+        assert_eq!(problem.direction, "up");
+        assert_eq!(problem.count, 3);
+        assert!((problem.avg_timing_error_beats - 0.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_cents_trail() {
+        let mut score = make_score(vec![(0.0, 1.0, 60), (1.0, 1.0, -1), (2.0, 1.0, 62)]);
+        score.notes[1].is_rest = true;
+        let trail = vec![
+            PitchTrailPoint { beat: 0.5, midi_float: 60.12 },
+            PitchTrailPoint { beat: 1.5, midi_float: 61.0 },
+            PitchTrailPoint { beat: 2.25, midi_float: 61.9 },
+        ];
+        let series = cents_trail(&trail, &score);
+        assert_eq!(series.len(), 3);
+        assert_eq!(series[0].0, 0.5);
+        assert!((series[0].1.unwrap() - 12.0).abs() < 1e-6);
+        assert_eq!(series[1].1, None);
+        assert!((series[2].1.unwrap() + 10.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_analyze_measure_range() {
+        let mut score = make_score(vec![
+            (0.0, 2.0, 60),
+            (2.0, 2.0, 62),
+            (4.0, 2.0, 64),
+            (6.0, 2.0, 65),
+            (8.0, 4.0, 67),
+        ]);
+        for note in &mut score.notes {
+            note.measure_number = (note.start_beat / 4.0) as u32 + 1;
+        }
+        score.measures = (0..3).map(|i| four_four(i + 1, i as f64 * 4.0)).collect();
+        score.total_beats = 12.0;
+
+        // Measures 2-3 played from beat 0
+        let played: Vec<PlayedNote> = [(0.0, 64), (2.0, 65), (4.0, 67)]
+            .iter()
+            .map(|&(onset_beat, midi)| PlayedNote {
+                onset_beat,
+                midi_float: midi as f64,
+                midi_rounded: midi,
+                confidence: 0.9,
+            })
+            .collect();
+        let analysis = analyze_measure_range(&score, 2, 3, &played, 50.0, 0.3);
+        assert_eq!(analysis.total_notes, 3);
+        assert_eq!(analysis.notes_correct, 3);
+        assert_eq!(analysis.note_results[0].target_beat, 0.0);
+
+        let slice = score.slice_measures(2, 3);
+        assert_eq!(slice.total_beats, 8.0);
+        assert_eq!(slice.measures[1].start_beat, 4.0);
+        assert_eq!(slice.measures[1].number, 3);
+    }
+
+    #[test]
+    fn test_airy_note_feedback() {
+        let score = make_score(vec![(0.0, 1.0, 60), (1.0, 1.0, 62)]);
+        let played: Vec<PlayedNote> = [(0.0, 60), (1.0, 62)]
+            .iter()
+            .map(|&(onset_beat, midi)| PlayedNote {
+                onset_beat,
+                midi_float: midi as f64,
+                midi_rounded: midi,
+                confidence: 0.6,
+            })
+            .collect();
+        let trail: Vec<PitchTrailPoint> = (0..10)
+            .map(|i| PitchTrailPoint {
+                beat: i as f64 * 0.2,
+                midi_float: if i < 5 { 60.0 } else { 62.0 },
+            })
+            .collect();
+        // The first note never rises above 0.6 confidence; the second is solid
+        let confidences: Vec<f64> = (0..10).map(|i| if i < 5 { 0.6 } else { 0.95 }).collect();
+
+        let analysis = analyze_performance_with_confidences(
+            &score,
+            &played,
+            50.0,
+            0.3,
+            Some(&trail),
+            &confidences,
+            &AnalysisOptions::default(),
+        );
+        assert_eq!(analysis.notes_correct, 2);
+        assert!(analysis.technique_feedback.iter().any(|f| f.starts_with("1 note sounded airy")));
+
+        // Without confidences there is nothing to judge
+        let plain = analyze_performance_with_trail(&score, &played, 50.0, 0.3, Some(&trail));
+        assert!(!plain.technique_feedback.iter().any(|f| f.contains("airy")));
+    }
+
+    #[test]
+    fn test_analyze_measure() {
+        let mut score = make_score(vec![(0.0, 2.0, 60), (2.0, 2.0, 62), (4.0, 2.0, 64), (6.0, 2.0, 65)]);
+        score.notes[2].measure_number = 2;
+        score.notes[3].measure_number = 2;
+        score.measures = vec![four_four(1, 0.0), four_four(2, 4.0)];
+        score.total_beats = 8.0;
+        // Measure 1 played a half step sharp, measure 2 clean
+        let played: Vec<PlayedNote> = [(0.0, 61), (2.0, 63), (4.0, 64), (6.0, 65)]
+            .iter()
+            .map(|&(onset_beat, midi)| PlayedNote {
+                onset_beat,
+                midi_float: midi as f64,
+                midi_rounded: midi,
+                confidence: 0.9,
+            })
+            .collect();
+
+        let second = analyze_measure(&score, &played, 2, 50.0, 0.3);
+        assert_eq!(second.total_notes, 2);
+        assert_eq!(second.notes_correct, 2);
+        assert_eq!(second.note_results[0].target_beat, 4.0);
+
+        let first = analyze_measure(&score, &played, 1, 50.0, 0.3);
+        assert_eq!(first.total_notes, 2);
+        assert_eq!(first.notes_wrong_pitch, 2);
+    }
+
+    #[test]
+    fn test_analyzer_context_matches_stateless() {
+        let mut score = make_score(vec![(0.0, 1.0, 60), (1.0, 1.0, -1), (2.0, 1.0, 64), (3.0, 1.0, 67)]);
+        score.notes[1].is_rest = true;
+        score.measures = vec![four_four(1, 0.0)];
+        let context = AnalyzerContext::new(&score);
+        assert_eq!(context.targets().len(), 3);
+
+        let options = AnalysisOptions {
+            weight_timing_by_beat: true,
+            ..Default::default()
+        };
+        // Growing takes, as in live scoring, against the one context
+        let take = [(0.0, 60.2), (2.1, 63.5), (2.9, 67.0)];
+        for n in 0..=take.len() {
+            let played: Vec<PlayedNote> = take[..n]
+                .iter()
+                .map(|&(onset_beat, midi_float)| PlayedNote {
+                    onset_beat,
+                    midi_float,
+                    midi_rounded: midi_float.round() as i32,
+                    confidence: 0.9,
+                })
+                .collect();
+            let cached = context.analyze(&played, 50.0, 0.3, None, &[], &options);
+            let stateless = analyze_performance_with_options(&score, &played, 50.0, 0.3, None, &options);
+            assert_eq!(
+                serde_json::to_value(&cached).unwrap(),
+                serde_json::to_value(&stateless).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_calibrate_reference() {
+        let midi_442 = 69.0 + 12.0 * (442.0f64 / 440.0).log2();
+        let trail: Vec<PitchTrailPoint> = (0..40)
+            .map(|i| PitchTrailPoint {
+                beat: i as f64 * 0.1,
+                midi_float: midi_442 + if i % 2 == 0 { 0.01 } else { -0.01 },
+            })
+            .collect();
+        assert!((calibrate_reference(&trail, 69) - 442.0).abs() < 0.5);
+        assert_eq!(calibrate_reference(&[], 69), 440.0);
+    }
+
+    #[test]
+    fn test_warmup_notes_excluded() {
+        let score = make_score(vec![(0.0, 1.0, 60), (1.0, 1.0, 62), (2.0, 1.0, 64), (3.0, 1.0, 65)]);
+        // Fluffed first note, the rest clean
+        let played: Vec<PlayedNote> = [(0.0, 61.0), (1.0, 62.0), (2.0, 64.0), (3.0, 65.0)]
+            .iter()
+            .map(|&(onset_beat, midi_float)| PlayedNote {
+                onset_beat,
+                midi_float,
+                midi_rounded: midi_float as i32,
+                confidence: 0.9,
+            })
+            .collect();
+        let options = AnalysisOptions {
+            warmup_notes: 1,
+            ..Default::default()
+        };
+
+        let all = analyze_performance(&score, &played, 50.0, 0.3);
+        let settled = analyze_performance_with_options(&score, &played, 50.0, 0.3, None, &options);
+        assert_eq!(all.notes_correct, 3);
+        assert_eq!(settled.total_notes, 3);
+        assert_eq!(settled.notes_correct, 3);
+        assert_eq!(settled.note_results[0].target_midi, 62);
+        assert!(settled.overall_score > all.overall_score);
+    }
+
+    #[test]
+    fn test_detect_constant_offset_whole_step_low() {
+        let score = make_score(vec![(0.0, 1.0, 60), (1.0, 1.0, 62), (2.0, 1.0, 64), (3.0, 1.0, 65)]);
+        let shifted = |offsets: [f64; 4]| -> Vec<PlayedNote> {
+            score
+                .notes
+                .iter()
+                .zip(offsets)
+                .map(|(n, off)| PlayedNote {
+                    onset_beat: n.start_beat + 0.05,
+                    midi_float: n.midi as f64 + off,
+                    midi_rounded: n.midi + off as i32,
+                    confidence: 0.9,
+                })
+                .collect()
+        };
+
+        assert_eq!(detect_constant_offset(&score, &shifted([-2.0, -2.1, -1.9, -2.0])), Some(-2));
+        // One slip doesn't hide the transposition
+        assert_eq!(detect_constant_offset(&score, &shifted([-2.0, 0.0, -2.0, -2.0])), Some(-2));
+        assert_eq!(detect_constant_offset(&score, &shifted([0.0; 4])), None);
+        assert_eq!(detect_constant_offset(&score, &shifted([-2.0, 1.0, 0.0, 3.0])), None);
+        assert_eq!(detect_constant_offset(&score, &[]), None);
+    }
+
+    #[test]
+    fn test_tempo_recovery_after_rush() {
+        let score = make_score((0..9).map(|i| (i as f64, 1.0, 60 + i)).collect());
+        // Rushing by a quarter beat, easing off, then locked in
+        let offsets = [-0.25, -0.2, -0.2, -0.1, -0.05, 0.0, 0.02, 0.0, -0.01];
+        let played: Vec<PlayedNote> = score
+            .notes
+            .iter()
+            .zip(offsets)
+            .map(|(n, off)| PlayedNote {
+                onset_beat: n.start_beat + off,
+                midi_float: n.midi as f64,
+                midi_rounded: n.midi,
+                confidence: 0.9,
+            })
+            .collect();
+
+        let analysis = analyze_performance(&score, &played, 50.0, 0.3);
+        assert!(analysis.tempo_recovered);
+        assert!(analysis.feedback.iter().any(|f| f.contains("Good recovery: you rushed")));
+
+        // Rushing throughout is not a recovery
+        let rushed: Vec<PlayedNote> = played
+            .iter()
+            .map(|p| PlayedNote { onset_beat: p.onset_beat.round() - 0.2, ..p.clone() })
+            .collect();
+        assert!(!analyze_performance(&score, &rushed, 50.0, 0.3).tempo_recovered);
+    }
+
+    #[test]
+    fn test_played_count_and_silence_ratio() {
+        let score = make_score((0..8).map(|i| (i as f64, 1.0, 60 + i)).collect());
+        let note = |onset_beat: f64, midi: i32| PlayedNote {
+            onset_beat,
+            midi_float: midi as f64,
+            midi_rounded: midi,
+            confidence: 0.9,
+        };
+
+        // One note at the start, then nothing: 7 of 8 beats silent
+        let analysis = analyze_performance(&score, &[note(0.0, 60)], 50.0, 0.3);
+        assert_eq!(analysis.played_notes_count, 1);
+        assert!((analysis.silence_ratio - 0.875).abs() < 1e-9);
+
+        // Every note played, all of them wrong: active, not silent
+        let wrong: Vec<PlayedNote> = (0..8).map(|i| note(i as f64, 50 + i)).collect();
+        let analysis = analyze_performance(&score, &wrong, 50.0, 0.3);
+        assert_eq!(analysis.notes_correct, 0);
+        assert_eq!(analysis.played_notes_count, 8);
+        assert_eq!(analysis.silence_ratio, 0.0);
+
+        // With a trail, sound is measured from the trail itself
+        let trail: Vec<PitchTrailPoint> = (0..20)
+            .map(|i| PitchTrailPoint { beat: i as f64 * 0.1, midi_float: 60.0 })
+            .collect();
+        let analysis = analyze_performance_with_trail(
+            &score,
+            &[note(0.0, 60)],
+            50.0,
+            0.3,
+            Some(&trail),
+        );
+        assert!((analysis.silence_ratio - 0.75).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_longest_correct_streak() {
+        let results: Vec<NoteResult> = [
+            "correct", "correct", "wrong_pitch", "correct", "correct", "correct", "missed", "correct",
+        ]
+        .iter()
+        .enumerate()
+        .map(|(i, &status)| NoteResult {
+            target_midi: 60,
+            target_beat: i as f64,
+            status: status.to_string(),
+            played_midi: None,
+            pitch_error_cents: None,
+            timing_error_beats: None,
+            target_hz: 0.0,
+            played_hz: None,
+        })
+        .collect();
+        assert_eq!(longest_correct_streak(&results), 3);
+        assert_eq!(longest_correct_streak(&results[..2]), 2);
+        assert_eq!(longest_correct_streak(&[]), 0);
+    }
+
+    #[test]
+    fn test_pitch_consistency() {
+        let results = |errors: &[(i32, f64)]| -> Vec<NoteResult> {
+            errors
+                .iter()
+                .enumerate()
+                .map(|(i, &(midi, cents))| NoteResult {
+                    target_midi: midi,
+                    target_beat: i as f64,
+                    status: "correct".to_string(),
+                    played_midi: Some(midi as f64 + cents / 100.0),
+                    pitch_error_cents: Some(cents),
+                    timing_error_beats: Some(0.0),
+                    target_hz: 0.0,
+                    played_hz: None,
+                })
+                .collect()
+        };
+
+        // Consistently 10 cents sharp is perfectly consistent
+        let tight = results(&[(60, 10.0), (60, 10.0), (67, -5.0), (67, -5.0)]);
+        let spread = results(&[(60, -20.0), (60, 20.0), (67, -30.0), (67, 10.0)]);
+        assert_eq!(pitch_consistency(&tight), 0.0);
+        assert_eq!(pitch_consistency(&spread), 20.0);
+        // Unrepeated pitches don't count
+        assert_eq!(pitch_consistency(&results(&[(60, 30.0), (62, -30.0)])), 0.0);
+    }
+
+    #[test]
+    fn test_compare_performances_ignores_score() {
+        let score = make_score(vec![
+            (0.0, 1.0, 60),
+            (1.0, 1.0, 62),
+            (2.0, 1.0, 64),
+            (3.0, 1.0, 65),
+        ]);
+        // The teacher plays 30 cents sharp and lays back a third of a beat;
+        // the student copies that, starting two beats later
+        let take = |offset: f64| -> Vec<PlayedNote> {
+            score
+                .notes
+                .iter()
+                .map(|n| PlayedNote {
+                    onset_beat: n.start_beat + offset + 0.3,
+                    midi_float: n.midi as f64 + 0.3,
+                    midi_rounded: n.midi,
+                    confidence: 0.9,
+                })
+                .collect()
+        };
+        let reference = take(0.0);
+        let student = take(2.0);
+
+        let against_score = analyze_performance(&score, &reference, 25.0, 0.2);
+        assert_eq!(against_score.notes_correct, 0);
+
+        let analysis = compare_performances(&reference, &student, 25.0, 0.2);
+        assert_eq!(analysis.total_notes, 4);
+        assert_eq!(analysis.notes_correct, 4);
+        assert!(analysis.overall_score > 95.0, "{}", analysis.overall_score);
+        assert!(analysis.avg_pitch_error_cents.abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_summarize_feedback() {
+        let score = make_score(vec![
+            (0.0, 1.0, 60),
+            (1.0, 1.0, 67),
+            (2.0, 1.0, 60),
+            (3.0, 1.0, 67),
+            (4.0, 1.0, 60),
+            (5.0, 1.0, 67),
+        ]);
+        // Every G4 after the leap up is 40 cents sharp; one C4 is missed
+        let played: Vec<PlayedNote> = score
+            .notes
+            .iter()
+            .filter(|n| n.start_beat != 4.0)
+            .map(|n| {
+                let midi_float = n.midi as f64 + if n.midi == 67 { 0.4 } else { 0.0 };
+                PlayedNote {
+                    onset_beat: n.start_beat,
+                    midi_float,
+                    midi_rounded: n.midi,
+                    confidence: 0.9,
+                }
+            })
+            .collect();
+
+        let analysis = analyze_performance(&score, &played, 50.0, 0.3);
+        let summary = summarize_feedback(&analysis);
+        assert!(analysis.feedback.len() > 2);
+        assert!(summary.starts_with(&analysis.feedback[0]));
+        assert!(summary.contains("83%"), "{}", summary);
+        assert!(summary.contains("from C4 to G4"), "{}", summary);
+        assert!(!summary.contains("missed"), "{}", summary);
+
+        // A clean take is just the score line
+        let clean: Vec<PlayedNote> = score
+            .notes
+            .iter()
+            .map(|n| PlayedNote {
+                onset_beat: n.start_beat,
+                midi_float: n.midi as f64,
+                midi_rounded: n.midi,
+                confidence: 0.9,
+            })
+            .collect();
+        let analysis = analyze_performance(&score, &clean, 50.0, 0.3);
+        assert_eq!(summarize_feedback(&analysis), analysis.feedback[0]);
+    }
+
+    #[test]
+    fn test_readiness_scales_with_difficulty() {
+        use crate::exercises::generators::generate_with_options;
+
+        let clean_take = |score: &Score| {
+            let played: Vec<PlayedNote> = score
+                .notes
+                .iter()
+                .filter(|n| !n.is_rest)
+                .map(|n| PlayedNote {
+                    onset_beat: n.start_beat,
+                    midi_float: n.midi as f64,
+                    midi_rounded: n.midi,
+                    confidence: 0.9,
+                })
+                .collect();
+            analyze_performance(score, &played, 50.0, 0.3)
+        };
+
+        let easy = generate_with_options("long_tones", "C4", 60.0, Some(1), None).unwrap();
+        let hard = generate_with_options("octave_studies", "G4", 160.0, Some(4), None).unwrap();
+        let easy_take = clean_take(&easy);
+        let hard_take = clean_take(&hard);
+        assert_eq!(easy_take.overall_score, 100.0);
+        assert_eq!(hard_take.overall_score, 100.0);
+
+        let easy_readiness = readiness(&easy, &easy_take);
+        let hard_readiness = readiness(&hard, &hard_take);
+        assert!(hard_readiness > 75.0, "{}", hard_readiness);
+        assert!(easy_readiness < hard_readiness);
+        // Headroom left on the easy piece
+        assert!(100.0 - easy_readiness > 100.0 - hard_readiness + 10.0);
+    }
+
+    #[test]
+    fn test_note_result_frequencies() {
+        let score = make_score(vec![(0.0, 1.0, 69), (1.0, 1.0, 81), (2.0, 1.0, 72)]);
+        let played = vec![
+            PlayedNote { onset_beat: 0.0, midi_float: 69.0, midi_rounded: 69, confidence: 0.9 },
+            PlayedNote { onset_beat: 1.0, midi_float: 80.9, midi_rounded: 81, confidence: 0.9 },
+        ];
+
+        let analysis = analyze_performance(&score, &played, 50.0, 0.3);
+        let a4 = &analysis.note_results[0];
+        assert!((a4.target_hz - 440.0).abs() < 1e-9);
+        assert!((a4.played_hz.unwrap() - 440.0).abs() < 1e-9);
+        assert!((analysis.note_results[1].target_hz - 880.0).abs() < 1e-9);
+        assert!(analysis.note_results[1].played_hz.unwrap() < 880.0);
+        assert_eq!(analysis.note_results[2].played_hz, None);
+
+        let options = AnalysisOptions {
+            reference_hz: Some(442.0),
+            ..Default::default()
+        };
+        let analysis = analyze_performance_with_options(&score, &played, 50.0, 0.3, None, &options);
+        assert!((analysis.note_results[0].target_hz - 442.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_latency_compensation() {
+        let score = make_score((0..4).map(|i| (i as f64, 1.0, 60 + i)).collect());
+        // Every onset reported 0.2 beats after the note sounded
+        let played: Vec<PlayedNote> = score
+            .notes
+            .iter()
+            .map(|n| PlayedNote {
+                onset_beat: n.start_beat + 0.2,
+                midi_float: n.midi as f64,
+                midi_rounded: n.midi,
+                confidence: 0.9,
+            })
+            .collect();
+
+        let raw = analyze_performance(&score, &played, 50.0, 0.3);
+        assert_eq!(raw.timing_tendency, "late");
+
+        let options = AnalysisOptions {
+            latency_beats: 0.2,
+            ..Default::default()
+        };
+        let compensated = analyze_performance_with_options(&score, &played, 50.0, 0.3, None, &options);
+        assert_eq!(compensated.timing_tendency, "on_time");
+        assert!(compensated.avg_timing_error_beats.abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_trill_extra_notes_not_penalized() {
+        let mut score = make_score(vec![(0.0, 2.0, 72), (2.0, 1.0, 74)]);
+        // A trill starting on the upper note: D C D C ... then the written D
+        let mut played: Vec<PlayedNote> = (0..16)
+            .map(|i| {
+                let midi = if i % 2 == 0 { 74 } else { 72 };
+                PlayedNote {
+                    onset_beat: i as f64 * 0.125,
+                    midi_float: midi as f64,
+                    midi_rounded: midi,
+                    confidence: 0.9,
+                }
+            })
+            .collect();
+        played.push(PlayedNote { onset_beat: 2.0, midi_float: 74.0, midi_rounded: 74, confidence: 0.9 });
+
+        let plain = analyze_performance(&score, &played, 50.0, 0.3);
+        assert_eq!(plain.note_results[0].status, "wrong_pitch");
 
-    // Track errors per interval (from_midi, to_midi)
-    let mut interval_errors: HashMap<(i32, i32), Vec<f64>> = HashMap::new();
+        score.notes[0].ornaments = vec!["trill-mark".to_string()];
+        let trilled = analyze_performance(&score, &played, 50.0, 0.3);
+        assert_eq!(trilled.notes_correct, 2);
+        assert_eq!(trilled.note_results[0].timing_error_beats, Some(0.0));
+        assert_eq!(trilled.note_results[1].played_midi, Some(74.0));
+        assert_eq!(trilled.overall_score, 100.0);
+    }
 
-    for i in 1..results.len() {
-        let prev = &results[i - 1];
-        let curr = &results[i];
+    #[test]
+    fn test_partial_credit_near_miss() {
+        let score = make_score(vec![(0.0, 1.0, 60), (1.0, 1.0, 62)]);
+        let overall = |cents: f64| {
+            let played = vec![
+                PlayedNote { onset_beat: 0.0, midi_float: 60.0, midi_rounded: 60, confidence: 0.9 },
+                PlayedNote {
+                    onset_beat: 1.0,
+                    midi_float: 62.0 + cents / 100.0,
+                    midi_rounded: 62,
+                    confidence: 0.9,
+                },
+            ];
+            analyze_performance(&score, &played, 50.0, 0.3).overall_score
+        };
 
-        // Only analyze intervals where both notes were played
-        if let (Some(_prev_cents), Some(curr_cents)) =
-            (prev.pitch_error_cents, curr.pitch_error_cents)
-        {
-            if curr_cents.abs() > tolerance_cents * 0.5 {
-                let key = (prev.target_midi, curr.target_midi);
-                interval_errors.entry(key).or_default().push(curr_cents);
-            }
-        }
+        // Either side of the tolerance scores almost the same
+        let inside = overall(49.0);
+        let near_miss = overall(55.0);
+        let far_miss = overall(200.0);
+        assert!(inside - near_miss < 5.0, "{} vs {}", inside, near_miss);
+        assert!(near_miss - far_miss > 20.0, "{} vs {}", near_miss, far_miss);
     }
 
-    let mut problems: Vec<IntervalProblem> = Vec::new();
-    for ((from_midi, to_midi), errors) in &interval_errors {
-        if errors.len() < 2 {
-            continue; // Need at least 2 occurrences to call it a pattern
-        }
-        let avg = errors.iter().sum::<f64>() / errors.len() as f64;
-        if avg.abs() > 20.0 {
-            let direction = if to_midi > from_midi { "up" } else { "down" };
-            problems.push(IntervalProblem {
-                from_note: midi_to_name(*from_midi),
-                to_note: midi_to_name(*to_midi),
-                direction: direction.to_string(),
-                avg_error_cents: avg,
-                count: errors.len() as u32,
-            });
-        }
+    #[test]
+    fn test_lost_place_feedback() {
+        let score = make_score((0..6).map(|i| (i as f64, 1.0, 60 + i)).collect());
+        // Played the first and last notes, dropped out for the four between
+        let played = vec![
+            PlayedNote { onset_beat: 0.0, midi_float: 60.0, midi_rounded: 60, confidence: 0.9 },
+            PlayedNote { onset_beat: 5.0, midi_float: 65.0, midi_rounded: 65, confidence: 0.9 },
+        ];
+        let analysis = analyze_performance(&score, &played, 50.0, 0.3);
+        assert_eq!(analysis.notes_missed, 4);
+        assert!(analysis.feedback.iter().any(|f| f.contains("4 notes in a row") && f.contains("lost your place")));
+        assert!(!analysis.feedback.iter().any(|f| f.contains("play through the whole piece")));
+
+        // Scattered misses keep the generic message
+        let scattered = vec![
+            PlayedNote { onset_beat: 0.0, midi_float: 60.0, midi_rounded: 60, confidence: 0.9 },
+            PlayedNote { onset_beat: 2.0, midi_float: 62.0, midi_rounded: 62, confidence: 0.9 },
+            PlayedNote { onset_beat: 4.0, midi_float: 64.0, midi_rounded: 64, confidence: 0.9 },
+        ];
+        let analysis = analyze_performance(&score, &scattered, 50.0, 0.3);
+        assert!(!analysis.feedback.iter().any(|f| f.contains("lost your place")));
     }
 
-    // Sort by severity
-    problems.sort_by(|a, b| {
-        b.avg_error_cents
-            .abs()
-            .partial_cmp(&a.avg_error_cents.abs())
-            .unwrap_or(std::cmp::Ordering::Equal)
-    });
-    problems.truncate(3); // Top 3 problem intervals
-    problems
-}
+    #[test]
+    fn test_pitch_only_ignores_rhythm() {
+        let score = make_score(vec![
+            (0.0, 1.0, 60),
+            (1.0, 1.0, 62),
+            (2.0, 1.0, 64),
+            (3.0, 1.0, 65),
+            (4.0, 1.0, 67),
+        ]);
+        // Right notes, no rhythm: rushed, then a long stall, then dragging
+        let onsets = [0.0, 0.3, 0.5, 5.5, 9.0];
+        let played: Vec<PlayedNote> = score
+            .notes
+            .iter()
+            .zip(onsets)
+            .map(|(n, onset_beat)| PlayedNote {
+                onset_beat,
+                midi_float: n.midi as f64,
+                midi_rounded: n.midi,
+                confidence: 0.9,
+            })
+            .collect();
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        let timed = analyze_performance(&score, &played, 50.0, 0.3);
+        assert!(timed.notes_missed >= 3);
 
-    fn make_score(notes: Vec<(f64, f64, i32)>) -> Score {
-        Score {
-            tempo: 120.0,
-            notes: notes
-                .into_iter()
-                .map(|(beat, dur, midi)| NoteEvent {
-                    start_beat: beat,
-                    duration_beats: dur,
-                    midi,
-                    is_rest: false,
-                    measure_number: 1,
-                    note_type: "quarter".to_string(),
-                })
-                .collect(),
-            measures: vec![],
-            key_fifths: 0,
-            transpose: None,
-            title: None,
-            total_beats: 4.0,
-        }
+        let options = AnalysisOptions {
+            pitch_only: true,
+            ..AnalysisOptions::default()
+        };
+        let analysis =
+            analyze_performance_with_options(&score, &played, 50.0, 0.3, None, &options);
+        assert_eq!(analysis.notes_correct, 5);
+        assert_eq!(analysis.timing_tendency, "on_time");
+        assert!(analysis.timing_problem_intervals.is_empty());
+        assert!(analysis.note_results.iter().all(|r| r.timing_error_beats.is_none()));
+        assert_eq!(analysis.overall_score, 100.0);
+
+        // Order still matters: a skipped note shifts the rest onto wrong targets
+        let skipped: Vec<PlayedNote> = played.iter().skip(1).cloned().collect();
+        let analysis =
+            analyze_performance_with_options(&score, &skipped, 50.0, 0.3, None, &options);
+        assert_eq!(analysis.notes_wrong_pitch, 4);
+        assert_eq!(analysis.notes_missed, 1);
     }
 
     #[test]
-    fn test_perfect_performance() {
+    fn test_sight_reading_reattempt_flagged() {
         let score = make_score(vec![(0.0, 1.0, 60), (1.0, 1.0, 62), (2.0, 1.0, 64)]);
-        let played = vec![
-            PlayedNote {
-                onset_beat: 0.0,
-                midi_float: 60.0,
-                midi_rounded: 60,
-                confidence: 0.9,
-            },
-            PlayedNote {
-                onset_beat: 1.0,
-                midi_float: 62.0,
-                midi_rounded: 62,
-                confidence: 0.9,
-            },
-            PlayedNote {
-                onset_beat: 2.0,
-                midi_float: 64.0,
-                midi_rounded: 64,
+        // Cracked first note, then a quick fix before moving on
+        let played: Vec<PlayedNote> = [(-0.1, 61.0), (0.05, 60.0), (1.0, 62.0), (2.0, 64.0)]
+            .iter()
+            .map(|&(onset_beat, midi_float)| PlayedNote {
+                onset_beat,
+                midi_float,
+                midi_rounded: midi_float as i32,
                 confidence: 0.9,
-            },
+            })
+            .collect();
+        let options = AnalysisOptions {
+            sight_reading: true,
+            ..Default::default()
+        };
+
+        let practice = analyze_performance(&score, &played, 50.0, 0.3);
+        let sight = analyze_performance_with_options(&score, &played, 50.0, 0.3, None, &options);
+        assert_eq!(practice.notes_correct, 3);
+        assert_eq!(practice.reattempts, 0);
+        assert_eq!(sight.notes_wrong_pitch, 1);
+        assert_eq!(sight.notes_correct, 2);
+        assert_eq!(sight.reattempts, 1);
+        assert!(sight.overall_score < practice.overall_score);
+    }
+
+    #[test]
+    fn test_just_intonation_major_third() {
+        let score = make_score(vec![(0.0, 1.0, 60), (1.0, 1.0, 64)]);
+        // E4 tuned as a just major third above C: ~14 cents flat of equal
+        let played = vec![
+            PlayedNote { onset_beat: 0.0, midi_float: 60.0, midi_rounded: 60, confidence: 0.9 },
+            PlayedNote { onset_beat: 1.0, midi_float: 63.86, midi_rounded: 64, confidence: 0.9 },
         ];
+        let just = AnalysisOptions {
+            tuning: TuningSystem::Just { tonic_midi: 48 },
+            ..Default::default()
+        };
 
-        let result = analyze_performance(&score, &played, 50.0, 0.25);
-        assert_eq!(result.total_notes, 3);
-        assert_eq!(result.notes_correct, 3);
-        assert_eq!(result.notes_missed, 0);
-        assert_eq!(result.notes_wrong_pitch, 0);
-        assert!(result.overall_score > 70.0);
-        assert_eq!(result.pitch_tendency, "accurate");
-        assert_eq!(result.timing_tendency, "on_time");
+        let equal = analyze_performance(&score, &played, 10.0, 0.3);
+        assert_eq!(equal.note_results[1].status, "wrong_pitch");
+        assert!(equal.note_results[1].pitch_error_cents.unwrap() < -10.0);
+
+        let tuned = analyze_performance_with_options(&score, &played, 10.0, 0.3, None, &just);
+        assert_eq!(tuned.notes_correct, 2);
+        assert!(tuned.note_results[1].pitch_error_cents.unwrap().abs() < 1.0);
     }
 
     #[test]
-    fn test_missed_notes() {
-        let score = make_score(vec![(0.0, 1.0, 60), (1.0, 1.0, 62), (2.0, 1.0, 64)]);
-        let played = vec![PlayedNote {
-            onset_beat: 0.0,
-            midi_float: 60.0,
-            midi_rounded: 60,
-            confidence: 0.9,
-        }];
+    fn test_technique_attack_uses_tuning() {
+        // Bb3 as a just minor seventh over C: ~18 cents sharp of equal
+        let score = make_score(vec![(0.0, 2.0, 58)]);
+        let played =
+            vec![PlayedNote { onset_beat: 0.0, midi_float: 58.36, midi_rounded: 58, confidence: 0.9 }];
+        let trail: Vec<PitchTrailPoint> = (0..10)
+            .map(|i| PitchTrailPoint { beat: i as f64 * 0.2, midi_float: 58.36 })
+            .collect();
+        let just = AnalysisOptions {
+            tuning: TuningSystem::Just { tonic_midi: 48 },
+            ..Default::default()
+        };
 
-        let result = analyze_performance(&score, &played, 50.0, 0.25);
-        assert_eq!(result.notes_correct, 1);
-        assert_eq!(result.notes_missed, 2);
+        let equal = analyze_performance_with_trail(&score, &played, 50.0, 0.3, Some(&trail));
+        assert_eq!(equal.attack_quality, Some(0.0));
+        let tuned =
+            analyze_performance_with_options(&score, &played, 50.0, 0.3, Some(&trail), &just);
+        assert_eq!(tuned.attack_quality, Some(1.0));
     }
 
     #[test]
-    fn test_sharp_tendency() {
-        let score = make_score(vec![(0.0, 1.0, 60), (1.0, 1.0, 62)]);
+    fn test_articulation_feedback_held_staccato() {
+        let mut score = make_score(vec![(0.0, 1.0, 60), (1.0, 1.0, 62)]);
+        score.notes[0].articulations = vec!["staccato".to_string()];
+        score.notes[1].articulations = vec!["staccato".to_string()];
         let played = vec![
-            PlayedNote {
+            PlayedNote { onset_beat: 0.0, midi_float: 60.0, midi_rounded: 60, confidence: 0.9 },
+            PlayedNote { onset_beat: 1.0, midi_float: 62.0, midi_rounded: 62, confidence: 0.9 },
+        ];
+        // First note held through the whole beat, second released after a quarter beat
+        let mut trail: Vec<PitchTrailPoint> = (0..10)
+            .map(|i| PitchTrailPoint { beat: i as f64 * 0.1, midi_float: 60.0 })
+            .collect();
+        trail.extend((0..3).map(|i| PitchTrailPoint { beat: 1.0 + i as f64 * 0.1, midi_float: 62.0 }));
+
+        let result = analyze_performance_with_trail(&score, &played, 50.0, 0.25, Some(&trail));
+        assert_eq!(result.articulation_feedback.len(), 1);
+        assert!(result.articulation_feedback[0].starts_with("1 staccato note was held too long"));
+
+        let untracked = analyze_performance(&score, &played, 50.0, 0.25);
+        assert!(untracked.articulation_feedback.is_empty());
+    }
+
+    #[test]
+    fn test_profile_by_exercise() {
+        let score = make_score(vec![(0.0, 1.0, 60)]);
+        let take = |score_value: f64| {
+            let mut analysis = analyze_performance(&score, &[], 50.0, 0.25);
+            analysis.overall_score = score_value;
+            analysis
+        };
+        let results = vec![
+            ("major_scale".to_string(), take(90.0)),
+            ("lip_slurs".to_string(), take(40.0)),
+            ("major_scale".to_string(), take(80.0)),
+            ("lip_slurs".to_string(), take(60.0)),
+        ];
+        assert_eq!(
+            profile_by_exercise(&results),
+            vec![("major_scale".to_string(), 85.0), ("lip_slurs".to_string(), 50.0)]
+        );
+        assert!(profile_by_exercise(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_confidence_histogram() {
+        let played: Vec<PlayedNote> = [0.05, 0.3, 0.55, 0.6, 0.95, 1.0]
+            .iter()
+            .map(|&confidence| PlayedNote {
                 onset_beat: 0.0,
-                midi_float: 60.2,
+                midi_float: 60.0,
                 midi_rounded: 60,
-                confidence: 0.9,
-            },
-            PlayedNote {
-                onset_beat: 1.0,
-                midi_float: 62.3,
-                midi_rounded: 62,
-                confidence: 0.9,
-            },
+                confidence,
+            })
+            .collect();
+        assert_eq!(confidence_histogram(&played, 4), vec![1, 1, 2, 2]);
+        assert_eq!(confidence_histogram(&played, 1), vec![6]);
+        assert!(confidence_histogram(&played, 0).is_empty());
+    }
+
+    #[test]
+    fn test_unmetered_measure_relaxes_timing() {
+        let mut score = make_score(vec![(0.0, 1.0, 60), (4.0, 1.0, 62), (5.0, 1.0, 64)]);
+        score.notes[1].measure_number = 2;
+        score.notes[2].measure_number = 2;
+        let mut cadenza = four_four(2, 4.0);
+        cadenza.unmetered = true;
+        score.measures = vec![four_four(1, 0.0), cadenza];
+        let played = vec![
+            PlayedNote { onset_beat: 0.0, midi_float: 60.0, midi_rounded: 60, confidence: 0.9 },
+            PlayedNote { onset_beat: 4.6, midi_float: 62.0, midi_rounded: 62, confidence: 0.9 },
+            PlayedNote { onset_beat: 5.6, midi_float: 64.0, midi_rounded: 64, confidence: 0.9 },
         ];
 
-        let result = analyze_performance(&score, &played, 50.0, 0.25);
-        assert_eq!(result.notes_correct, 2);
-        assert_eq!(result.pitch_tendency, "sharp");
+        let result = analyze_performance(&score, &played, 50.0, 0.3);
+        assert_eq!(result.notes_correct, 3);
+        assert_eq!(result.avg_timing_error_beats, 0.0);
+        assert_eq!(result.timing_tendency, "on_time");
+
+        score.measures[1].unmetered = false;
+        let metered = analyze_performance(&score, &played, 50.0, 0.3);
+        assert_eq!(metered.notes_missed, 2);
     }
 
     #[test]
-    fn test_wrong_pitch() {
-        let score = make_score(vec![(0.0, 1.0, 60)]);
-        let played = vec![PlayedNote {
-            onset_beat: 0.0,
-            midi_float: 62.0,            // 200 cents off
-            midi_rounded: 62,
-            confidence: 0.9,
-        }];
+    fn test_articulation_rate() {
+        let score = make_score(vec![(0.0, 0.25, 60)]);
+        let at = |beats: &[f64]| -> Vec<PlayedNote> {
+            beats
+                .iter()
+                .map(|&onset_beat| PlayedNote {
+                    onset_beat,
+                    midi_float: 60.0,
+                    midi_rounded: 60,
+                    confidence: 0.9,
+                })
+                .collect()
+        };
 
-        let result = analyze_performance(&score, &played, 50.0, 0.25);
-        assert_eq!(result.notes_wrong_pitch, 1);
-        assert_eq!(result.notes_correct, 0);
+        // Sixteenths at 120 bpm: 8 notes per second, perfectly even
+        let even: Vec<f64> = (0..16).map(|i| i as f64 * 0.25).collect();
+        let report = articulation_rate(&at(&even), &score);
+        assert!((report.notes_per_second - 8.0).abs() < 1e-9);
+        assert!(report.interval_std_dev_seconds < 1e-9);
+
+        let ragged = articulation_rate(&at(&[0.0, 0.15, 0.5, 0.6, 1.0, 1.1, 1.5]), &score);
+        assert!(ragged.interval_std_dev_seconds > 0.05);
+
+        assert_eq!(articulation_rate(&at(&[0.0]), &score).notes_per_second, 0.0);
+    }
+
+    #[test]
+    fn test_evenness() {
+        let at = |beats: &[f64]| -> Vec<PlayedNote> {
+            beats
+                .iter()
+                .map(|&onset_beat| PlayedNote {
+                    onset_beat,
+                    midi_float: 60.0,
+                    midi_rounded: 60,
+                    confidence: 0.9,
+                })
+                .collect()
+        };
+
+        let even: Vec<f64> = (0..8).map(|i| i as f64 * 0.5).collect();
+        assert!(evenness(&at(&even)) < 1e-9);
+        // Same spacing at a different tempo, listed out of order
+        assert!(evenness(&at(&[3.0, 0.0, 1.0, 2.0])) < 1e-9);
+
+        let ragged = evenness(&at(&[0.0, 0.3, 1.0, 1.2, 2.0, 2.7, 3.0]));
+        assert!(ragged > 0.4, "ragged run scored {}", ragged);
+
+        assert_eq!(evenness(&at(&[0.0, 1.0])), 0.0);
     }
 
     #[test]
@@ -617,6 +2884,7 @@ mod tests {
             transpose: None,
             title: None,
             total_beats: 0.0,
+            wedges: Vec::new(),
         };
         let result = analyze_performance(&score, &[], 50.0, 0.25);
         assert_eq!(result.total_notes, 0);
@@ -663,6 +2931,225 @@ mod tests {
         assert!(result.breath_support.is_some());
     }
 
+    #[test]
+    fn test_attack_times_ms() {
+        let score = make_score(vec![(0.0, 1.0, 60), (1.0, 1.0, 62), (2.0, 1.0, 64)]);
+        let played: Vec<PlayedNote> = score.notes[..2]
+            .iter()
+            .map(|n| PlayedNote {
+                onset_beat: n.start_beat,
+                midi_float: n.midi as f64,
+                midi_rounded: n.midi,
+                confidence: 0.9,
+            })
+            .collect();
+        // A point every 0.05 beats (25 ms at 120 bpm). The C4 scoops up
+        // from 50 cents flat for 0.2 beats; the D4 is centered at once
+        let mut trail = Vec::new();
+        for i in 0..40 {
+            let beat = i as f64 * 0.05;
+            let midi_float = if beat < 0.2 {
+                59.5
+            } else if beat < 1.0 {
+                60.0
+            } else {
+                62.0
+            };
+            trail.push(PitchTrailPoint { beat, midi_float });
+        }
+
+        let analysis = analyze_performance_with_trail(&score, &played, 50.0, 0.3, Some(&trail));
+        let targets: Vec<&NoteEvent> = score.notes.iter().collect();
+        let equal = AnalysisOptions::default();
+        let times = attack_times_ms(&targets, &analysis.note_results, &trail, 120.0, &equal);
+        assert_eq!(times.len(), 2);
+        assert!((times[0] - 100.0).abs() < 1e-6, "{:?}", times);
+        assert_eq!(times[1], 0.0);
+        // Twice as slow, twice as long
+        let slow = attack_times_ms(&targets, &analysis.note_results, &trail, 60.0, &equal);
+        assert!((slow[0] - 200.0).abs() < 1e-6);
+
+        // Bb3 settled near its just minor seventh over C attacks at once
+        // under just intonation, but never settles against equal temperament
+        let score = make_score(vec![(0.0, 1.0, 58)]);
+        let trail: Vec<PitchTrailPoint> = (0..10)
+            .map(|i| PitchTrailPoint { beat: i as f64 * 0.1, midi_float: 58.36 })
+            .collect();
+        let just = AnalysisOptions {
+            tuning: TuningSystem::Just { tonic_midi: 48 },
+            ..Default::default()
+        };
+        let targets: Vec<&NoteEvent> = score.notes.iter().collect();
+        let results = &analysis.note_results[..1];
+        assert_eq!(attack_times_ms(&targets, results, &trail, 60.0, &just), vec![0.0]);
+        assert!(attack_times_ms(&targets, results, &trail, 60.0, &equal)[0] > 0.0);
+    }
+
+    #[test]
+    fn test_centering_cents() {
+        let score = make_score(vec![(0.0, 1.0, 60), (1.0, 1.0, 62), (2.0, 1.0, 64)]);
+        let played: Vec<PlayedNote> = score
+            .notes
+            .iter()
+            .map(|n| PlayedNote {
+                onset_beat: n.start_beat,
+                midi_float: n.midi as f64 + 0.1,
+                midi_rounded: n.midi,
+                confidence: 0.9,
+            })
+            .collect();
+        // Each note scoops up from a semitone flat, then sits 10 cents sharp
+        let mut trail = Vec::new();
+        for note in &score.notes {
+            for i in 0..10 {
+                let offset = if i < 3 { -1.0 } else { 0.1 };
+                trail.push(PitchTrailPoint {
+                    beat: note.start_beat + i as f64 * 0.1,
+                    midi_float: note.midi as f64 + offset,
+                });
+            }
+        }
+
+        let analysis = analyze_performance_with_trail(&score, &played, 50.0, 0.3, Some(&trail));
+        let centering = analysis.centering_cents.unwrap();
+        assert!((centering - 10.0).abs() < 1e-6, "{}", centering);
+        assert!(analyze_performance(&score, &played, 50.0, 0.3).centering_cents.is_none());
+
+        // Bb3 held exactly on its just minor seventh over C is centered
+        let score = make_score(vec![(0.0, 1.0, 58)]);
+        let just_bb = 58.176;
+        let played =
+            vec![PlayedNote { onset_beat: 0.0, midi_float: just_bb, midi_rounded: 58, confidence: 0.9 }];
+        let trail: Vec<PitchTrailPoint> = (0..10)
+            .map(|i| PitchTrailPoint { beat: i as f64 * 0.1, midi_float: just_bb })
+            .collect();
+        let just = AnalysisOptions {
+            tuning: TuningSystem::Just { tonic_midi: 48 },
+            ..Default::default()
+        };
+        let analysis =
+            analyze_performance_with_options(&score, &played, 50.0, 0.3, Some(&trail), &just);
+        assert!(analysis.centering_cents.unwrap().abs() < 1e-6);
+        assert!(analysis.note_results[0].pitch_error_cents.unwrap().abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_glissando_sweep_not_penalized() {
+        let mut score = make_score(vec![(0.0, 2.0, 60), (2.0, 2.0, 67)]);
+        let played: Vec<PlayedNote> = score
+            .notes
+            .iter()
+            .map(|n| PlayedNote {
+                onset_beat: n.start_beat,
+                midi_float: n.midi as f64,
+                midi_rounded: n.midi,
+                confidence: 0.9,
+            })
+            .collect();
+        // C held for a beat, a sweep up through the second beat, then G held
+        let trail: Vec<PitchTrailPoint> = (0..40)
+            .map(|i| {
+                let beat = i as f64 * 0.1;
+                let midi_float = if beat < 1.0 {
+                    60.0
+                } else if beat < 2.0 {
+                    60.0 + 7.0 * (beat - 1.0)
+                } else {
+                    67.0
+                };
+                PitchTrailPoint { beat, midi_float }
+            })
+            .collect();
+
+        let plain = analyze_performance_with_trail(&score, &played, 50.0, 0.3, Some(&trail));
+        assert!(plain.pitch_stability.unwrap() > 15.0);
+        assert!(plain.technique_feedback.iter().any(|f| f.contains("wobbles")));
+
+        score.notes[0].glissando_to = true;
+        let gliss = analyze_performance_with_trail(&score, &played, 50.0, 0.3, Some(&trail));
+        assert_eq!(gliss.notes_correct, 2);
+        assert!(gliss.pitch_stability.unwrap() < 1.0, "{:?}", gliss.pitch_stability);
+        assert!(gliss.centering_cents.unwrap().abs() < 1.0);
+        assert!(gliss.technique_feedback.is_empty(), "{:?}", gliss.technique_feedback);
+    }
+
+    #[test]
+    fn test_forcing_detected_on_sharp_unstable_high_note() {
+        let score = make_score(vec![(0.0, 2.0, 81), (2.0, 2.0, 81)]);
+        let take = |sharp: f64, wobble: f64| {
+            let played: Vec<PlayedNote> = score
+                .notes
+                .iter()
+                .map(|n| PlayedNote {
+                    onset_beat: n.start_beat,
+                    midi_float: n.midi as f64 + sharp,
+                    midi_rounded: n.midi,
+                    confidence: 0.9,
+                })
+                .collect();
+            let trail: Vec<PitchTrailPoint> = (0..40)
+                .map(|i| PitchTrailPoint {
+                    beat: i as f64 * 0.1,
+                    midi_float: 81.0 + sharp + if i % 2 == 0 { wobble } else { -wobble },
+                })
+                .collect();
+            analyze_performance_with_trail(&score, &played, 50.0, 0.3, Some(&trail))
+        };
+
+        // A5 pinched 30 cents sharp and wobbling 25 cents either way
+        let forced = take(0.3, 0.25);
+        assert!(forced.forcing_detected);
+        assert!(forced.technique_feedback.iter().any(|f| f.contains("forced")));
+
+        // Sharp but steady, or unsteady but centered: not forcing
+        assert!(!take(0.3, 0.02).forcing_detected);
+        assert!(!take(0.0, 0.25).forcing_detected);
+
+        // The same sharp wobble an octave lower is not in the high register
+        let low = make_score(vec![(0.0, 2.0, 69)]);
+        let played = vec![PlayedNote {
+            onset_beat: 0.0,
+            midi_float: 69.3,
+            midi_rounded: 69,
+            confidence: 0.9,
+        }];
+        let trail: Vec<PitchTrailPoint> = (0..20)
+            .map(|i| PitchTrailPoint {
+                beat: i as f64 * 0.1,
+                midi_float: 69.3 + if i % 2 == 0 { 0.25 } else { -0.25 },
+            })
+            .collect();
+        let analysis = analyze_performance_with_trail(&low, &played, 50.0, 0.3, Some(&trail));
+        assert!(!analysis.forcing_detected);
+    }
+
+    #[test]
+    fn test_segment_pitch_ignores_attack() {
+        let mut trail = Vec::new();
+        let mut confidences = Vec::new();
+        // Out-of-tune, low-confidence attack frames
+        for i in 0..3 {
+            trail.push(PitchTrailPoint { beat: i as f64 * 0.1, midi_float: 61.0 });
+            confidences.push(0.4);
+        }
+        // Steady portion centered on 60.0
+        for (i, midi) in [59.98, 60.0, 60.02, 60.0, 60.01, 59.99, 60.0].iter().enumerate() {
+            trail.push(PitchTrailPoint { beat: 0.3 + i as f64 * 0.1, midi_float: *midi });
+            confidences.push(0.9);
+        }
+
+        let pitch = segment_pitch(&trail, &confidences);
+        assert!((pitch - 60.0).abs() < 0.03, "Expected ~60.0, got {}", pitch);
+
+        let mean = trail.iter().map(|p| p.midi_float).sum::<f64>() / trail.len() as f64;
+        assert!(mean > 60.25, "Plain mean should be dragged sharp, got {}", mean);
+    }
+
+    #[test]
+    fn test_segment_pitch_empty() {
+        assert_eq!(segment_pitch(&[], &[]), 0.0);
+    }
+
     #[test]
     fn test_endurance_delta() {
         // 8 notes, first 4 perfect, last 4 missed
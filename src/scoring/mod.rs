@@ -1,2 +1,6 @@
 pub mod analyzer;
+pub mod segmentation;
+pub mod stats;
+pub mod tempo;
 pub mod types;
+pub mod validation;
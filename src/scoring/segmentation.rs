@@ -0,0 +1,327 @@
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+#[cfg(not(feature = "std"))]
+use crate::math::FloatMath;
+use crate::scoring::stats::melody;
+use crate::scoring::types::*;
+
+/// A gap between trail points longer than this is treated as silence.
+const SILENCE_GAP_BEATS: f64 = 0.25;
+/// How far from an expected onset a pitch change may land and still be
+/// accepted immediately as that note's entry.
+const GUIDE_WINDOW_BEATS: f64 = 0.3;
+/// Unexpected pitch changes must hold for this many points to start a note.
+const MIN_STABLE_POINTS: usize = 3;
+/// Played notes more than this many beats before the first target are
+/// pre-roll (tuning notes, warm-up) rather than an early entrance.
+const LEAD_IN_TOLERANCE_BEATS: f64 = 0.5;
+/// A played note on the opening pitch within this many beats of the first
+/// target marks the real start; anything before it is dropped.
+const OPENING_WINDOW_BEATS: f64 = 0.5;
+
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+    values[values.len() / 2]
+}
+
+/// Build a PlayedNote from the trail points of one segment. The trail
+/// carries no detector confidence, so segments report full confidence.
+fn finish_note(points: &[&PitchTrailPoint]) -> PlayedNote {
+    let mut pitches: Vec<f64> = points.iter().map(|p| p.midi_float).collect();
+    let midi_float = median(&mut pitches);
+    PlayedNote {
+        onset_beat: points[0].beat,
+        midi_float,
+        midi_rounded: midi_float.round() as i32,
+        confidence: 1.0,
+    }
+}
+
+/// Unguided segmentation, mirroring the live onset detection in the web app:
+/// a new note starts whenever the rounded pitch changes or after silence.
+pub fn segment_notes(trail: &[PitchTrailPoint]) -> Vec<PlayedNote> {
+    let mut notes = Vec::new();
+    let mut last: Option<(f64, i32)> = None;
+    for p in trail {
+        let rounded = p.midi_float.round() as i32;
+        let is_onset = match last {
+            Some((beat, midi)) => rounded != midi || p.beat - beat > SILENCE_GAP_BEATS,
+            None => true,
+        };
+        if is_onset {
+            notes.push(PlayedNote {
+                onset_beat: p.beat,
+                midi_float: p.midi_float,
+                midi_rounded: rounded,
+                confidence: 1.0,
+            });
+        }
+        last = Some((p.beat, rounded));
+    }
+    notes
+}
+
+/// Build played notes from MIDI controller events given as
+/// (time_seconds, note_number, is_on). Each note-on is paired with the next
+/// note-off on its pitch and becomes a note at its beat at `tempo` with full
+/// confidence. A note-on while its pitch is still sounding retriggers it,
+/// ending the earlier note; note-ons never released are dropped, as are
+/// note-offs with nothing to end. Events may arrive in any order, and a
+/// note-off sharing a timestamp with a note-on is applied first.
+pub fn played_from_midi(events: &[(f64, u8, bool)], tempo: f64) -> Vec<PlayedNote> {
+    let beats_per_second = tempo / 60.0;
+    let mut sorted = events.to_vec();
+    sorted.sort_by(|a, b| {
+        a.0.partial_cmp(&b.0)
+            .unwrap_or(Ordering::Equal)
+            .then(a.2.cmp(&b.2))
+    });
+
+    let mut sounding: BTreeMap<u8, f64> = BTreeMap::new();
+    let mut notes = Vec::new();
+    let mut push = |time: f64, note: u8| {
+        notes.push(PlayedNote {
+            onset_beat: time * beats_per_second,
+            midi_float: note as f64,
+            midi_rounded: note as i32,
+            confidence: 1.0,
+        })
+    };
+    for (time, note, is_on) in sorted {
+        let started = if is_on {
+            sounding.insert(note, time)
+        } else {
+            sounding.remove(&note)
+        };
+        if let Some(onset) = started {
+            push(onset, note);
+        }
+    }
+    notes.sort_by(|a, b| a.onset_beat.partial_cmp(&b.onset_beat).unwrap_or(Ordering::Equal));
+    notes
+}
+
+/// Drop played notes from before the piece starts: anything more than
+/// `LEAD_IN_TOLERANCE_BEATS` ahead of the first target, and, when the
+/// opening pitch is played near the first target, any stray notes leading
+/// up to it. Everything from the start of the piece on is kept, in order.
+pub fn trim_leading_noise(played: &[PlayedNote], score: &Score) -> Vec<PlayedNote> {
+    let Some(opening) = melody(score).first().copied() else {
+        return played.to_vec();
+    };
+    let earliest = opening.start_beat - LEAD_IN_TOLERANCE_BEATS;
+    let entrance = played
+        .iter()
+        .filter(|p| {
+            p.midi_rounded == opening.midi
+                && p.onset_beat >= earliest
+                && p.onset_beat <= opening.start_beat + OPENING_WINDOW_BEATS
+        })
+        .map(|p| p.onset_beat)
+        .min_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+    let cutoff = entrance.unwrap_or(earliest);
+    played.iter().filter(|p| p.onset_beat >= cutoff).cloned().collect()
+}
+
+/// Segment a pitch trail into notes using the score's expected onsets as
+/// priors. A pitch change near an expected onset to the expected pitch starts
+/// a note at once; any other change must be sustained for a few points, so
+/// brief smears between notes are folded into the surrounding note. Each
+/// note's pitch is the median of its points rather than its first frame.
+pub fn segment_notes_guided(trail: &[PitchTrailPoint], score: &Score) -> Vec<PlayedNote> {
+    let expected: Vec<(f64, i32)> = melody(score)
+        .iter()
+        .map(|n| (n.start_beat, n.midi))
+        .collect();
+
+    let mut notes = Vec::new();
+    let mut current: Vec<&PitchTrailPoint> = Vec::new();
+    let mut current_midi = 0;
+
+    for (i, p) in trail.iter().enumerate() {
+        let rounded = p.midi_float.round() as i32;
+        let Some(prev) = current.last() else {
+            current.push(p);
+            current_midi = rounded;
+            continue;
+        };
+
+        let after_silence = p.beat - prev.beat > SILENCE_GAP_BEATS;
+        let is_onset = after_silence
+            || (rounded != current_midi && {
+                let expected_here = expected
+                    .iter()
+                    .any(|&(beat, midi)| midi == rounded && (p.beat - beat).abs() <= GUIDE_WINDOW_BEATS);
+                let sustained = trail[i..]
+                    .iter()
+                    .take(MIN_STABLE_POINTS)
+                    .filter(|q| q.midi_float.round() as i32 == rounded)
+                    .count()
+                    == MIN_STABLE_POINTS;
+                expected_here || sustained
+            });
+
+        if is_onset {
+            notes.push(finish_note(&current));
+            current.clear();
+            current_midi = rounded;
+        }
+        current.push(p);
+    }
+    if !current.is_empty() {
+        notes.push(finish_note(&current));
+    }
+    notes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score_of(notes: &[(f64, i32)]) -> Score {
+        Score {
+            tempo: 120.0,
+            notes: notes
+                .iter()
+                .map(|&(start_beat, midi)| NoteEvent {
+                    start_beat,
+                    duration_beats: 1.0,
+                    midi,
+                    is_rest: false,
+                    measure_number: 1,
+                    note_type: "quarter".to_string(),
+                    microtone_cents: 0.0,
+                    articulations: Vec::new(),
+                    beam_group: None,
+                    dynamics: None,
+                    velocity: None,
+                    ornaments: Vec::new(),
+                    accidental: None,
+                    glissando_to: false,
+                    accompaniment: false,
+                })
+                .collect(),
+            measures: vec![],
+            key_fifths: 0,
+            transpose: None,
+            title: None,
+            total_beats: notes.len() as f64,
+            wedges: Vec::new(),
+        }
+    }
+
+    fn played(onset_beat: f64, midi: i32) -> PlayedNote {
+        PlayedNote { onset_beat, midi_float: midi as f64, midi_rounded: midi, confidence: 0.9 }
+    }
+
+    #[test]
+    fn test_trim_leading_noise() {
+        let score = score_of(&[(0.0, 60), (1.0, 62), (2.0, 64)]);
+        // A Bb tuning note two beats early, a split attack just before the
+        // opening C, then the piece with a wrong second note
+        let take = [
+            played(-2.0, 58),
+            played(-0.2, 61),
+            played(-0.05, 60),
+            played(1.0, 63),
+            played(2.0, 64),
+        ];
+        let trimmed = trim_leading_noise(&take, &score);
+        let onsets: Vec<f64> = trimmed.iter().map(|p| p.onset_beat).collect();
+        assert_eq!(onsets, vec![-0.05, 1.0, 2.0]);
+
+        // A wrong opening note with no correct one nearby is kept, but the
+        // pre-roll still goes
+        let take = [played(-3.0, 60), played(0.0, 59), played(1.0, 62)];
+        let trimmed = trim_leading_noise(&take, &score);
+        assert_eq!(trimmed.len(), 2);
+        assert_eq!(trimmed[0].midi_rounded, 59);
+    }
+
+    fn point(beat: f64, midi_float: f64) -> PitchTrailPoint {
+        PitchTrailPoint { beat, midi_float }
+    }
+
+    /// C4 -> D4 -> E4 slurred, with a smeared pass through the semitone
+    /// between each pair.
+    fn smeared_trail() -> Vec<PitchTrailPoint> {
+        let mut trail = Vec::new();
+        for (note, midi) in [60.0, 62.0, 64.0].iter().enumerate() {
+            let start = note as f64;
+            if note > 0 {
+                trail.push(point(start - 0.05, midi - 0.9));
+            }
+            for i in 0..5 {
+                trail.push(point(start + i as f64 * 0.18, midi + 0.02));
+            }
+        }
+        trail
+    }
+
+    #[test]
+    fn test_unguided_splits_on_smears() {
+        let notes = segment_notes(&smeared_trail());
+        assert_eq!(notes.len(), 5);
+    }
+
+    #[test]
+    fn test_guided_follows_score() {
+        let score = score_of(&[(0.0, 60), (1.0, 62), (2.0, 64)]);
+        let notes = segment_notes_guided(&smeared_trail(), &score);
+        assert_eq!(notes.len(), 3);
+        assert_eq!(
+            notes.iter().map(|n| n.midi_rounded).collect::<Vec<_>>(),
+            vec![60, 62, 64]
+        );
+        assert!((notes[1].onset_beat - 1.0).abs() < 1e-9);
+        assert!((notes[2].midi_float - 64.02).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_guided_keeps_sustained_wrong_note() {
+        // The player holds a wrong D#4 instead of D4: still its own note
+        let score = score_of(&[(0.0, 60), (1.0, 62)]);
+        let trail: Vec<PitchTrailPoint> = (0..10)
+            .map(|i| point(i as f64 * 0.2, if i < 5 { 60.0 } else { 63.0 }))
+            .collect();
+        let notes = segment_notes_guided(&trail, &score);
+        assert_eq!(notes.len(), 2);
+        assert_eq!(notes[1].midi_rounded, 63);
+    }
+
+    #[test]
+    fn test_played_from_midi() {
+        // A4 held from 0.5 s to 1.0 s at 120 bpm
+        let notes = played_from_midi(&[(0.5, 69, true), (1.0, 69, false)], 120.0);
+        assert_eq!(notes.len(), 1);
+        assert!((notes[0].onset_beat - 1.0).abs() < 1e-9);
+        assert_eq!(notes[0].midi_rounded, 69);
+        assert_eq!(notes[0].midi_float, 69.0);
+        assert_eq!(notes[0].confidence, 1.0);
+
+        // C5 retriggered while held, E5 overlapping it, then a C5 never
+        // released and a stray note-off
+        let events = [
+            (0.0, 72, true),
+            (0.5, 72, true),
+            (0.75, 76, true),
+            (1.0, 72, false),
+            (1.0, 72, true),
+            (1.25, 76, false),
+            (1.5, 79, false),
+        ];
+        let notes = played_from_midi(&events, 60.0);
+        let got: Vec<(f64, i32)> = notes.iter().map(|n| (n.onset_beat, n.midi_rounded)).collect();
+        assert_eq!(got, vec![(0.0, 72), (0.5, 72), (0.75, 76)]);
+    }
+
+    #[test]
+    fn test_guided_splits_after_silence() {
+        let score = score_of(&[(0.0, 60), (1.0, 60)]);
+        let trail = vec![point(0.0, 60.0), point(0.2, 60.0), point(1.0, 60.0), point(1.2, 60.0)];
+        assert_eq!(segment_notes_guided(&trail, &score).len(), 2);
+        assert!(segment_notes_guided(&[], &score).is_empty());
+    }
+}
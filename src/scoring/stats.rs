@@ -0,0 +1,278 @@
+use alloc::vec::Vec;
+
+#[cfg(not(feature = "std"))]
+use crate::math::FloatMath;
+use crate::scoring::types::*;
+
+/// Krumhansl-Kessler key profiles, indexed by pitch class above the tonic.
+const MAJOR_PROFILE: [f64; 12] = [6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88];
+const MINOR_PROFILE: [f64; 12] = [6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17];
+
+/// Melodic line of a score: played notes with chord members (notes sharing
+/// the previous note's onset) dropped.
+pub(crate) fn melody(score: &Score) -> Vec<&NoteEvent> {
+    let mut line: Vec<&NoteEvent> = Vec::new();
+    for note in score.notes.iter().filter(|n| n.is_played()) {
+        if let Some(prev) = line.last() {
+            if note.start_beat == prev.start_beat {
+                continue;
+            }
+        }
+        line.push(note);
+    }
+    line
+}
+
+/// Largest melodic leap between consecutive notes as
+/// (from_midi, to_midi, semitone_span). Rests and chord members are skipped.
+/// Returns `None` when the score has fewer than two melody notes.
+pub fn hardest_interval(score: &Score) -> Option<(i32, i32, i32)> {
+    let line = melody(score);
+    let mut hardest: Option<(i32, i32, i32)> = None;
+    for pair in line.windows(2) {
+        let span = (pair[1].midi - pair[0].midi).abs();
+        if hardest.is_none_or(|(_, _, best)| span > best) {
+            hardest = Some((pair[0].midi, pair[1].midi, span));
+        }
+    }
+    hardest
+}
+
+/// Non-rest notes per measure as (measure_number, note_count), in measure
+/// order. Measures listed in `score.measures` with no notes report 0.
+pub fn note_density(score: &Score) -> Vec<(u32, usize)> {
+    use alloc::collections::BTreeMap;
+
+    let mut counts: BTreeMap<u32, usize> = score.measures.iter().map(|m| (m.number, 0)).collect();
+    for note in score.notes.iter().filter(|n| !n.is_rest) {
+        *counts.entry(note.measure_number).or_default() += 1;
+    }
+    counts.into_iter().collect()
+}
+
+/// Length in seconds of each continuous sounding span (a phrase between
+/// rests), in score order, for breath planning.
+pub fn phrase_durations_seconds(score: &Score) -> Vec<f64> {
+    let seconds_per_beat = 60.0 / score.tempo;
+    let mut phrases = Vec::new();
+    let mut span: Option<(f64, f64)> = None;
+    for note in &score.notes {
+        if note.is_rest {
+            if let Some((start, end)) = span.take() {
+                phrases.push((end - start) * seconds_per_beat);
+            }
+            continue;
+        }
+        let note_end = note.start_beat + note.duration_beats;
+        span = Some(match span {
+            Some((start, end)) => (start, end.max(note_end)),
+            None => (note.start_beat, note_end),
+        });
+    }
+    if let Some((start, end)) = span {
+        phrases.push((end - start) * seconds_per_beat);
+    }
+    phrases
+}
+
+/// Sounding notes per second at which speed difficulty saturates
+/// (sixteenths at 90 bpm).
+const HARD_NOTES_PER_SECOND: f64 = 6.0;
+/// Written pitches above G4 get harder up to C6.
+const COMFORTABLE_TOP_MIDI: i32 = 67;
+const HARD_TOP_MIDI: i32 = 84;
+
+/// Rough difficulty of a score in 0.0..=1.0: the mean of its range (up to two
+/// octaves), largest leap (up to an octave), note rate and highest pitch
+/// (G4 to C6), each scaled to 0..=1. Returns 0.0 for a score with no notes.
+pub fn estimate_difficulty(score: &Score) -> f64 {
+    let Some((low, high)) = score.midi_range() else {
+        return 0.0;
+    };
+    let range = (high - low) as f64 / 24.0;
+    let leap = hardest_interval(score).map_or(0.0, |(_, _, span)| span as f64 / 12.0);
+    let seconds = score.total_beats * 60.0 / score.tempo;
+    let rate = if seconds > 0.0 {
+        melody(score).len() as f64 / seconds / HARD_NOTES_PER_SECOND
+    } else {
+        0.0
+    };
+    let height = (high - COMFORTABLE_TOP_MIDI) as f64 / (HARD_TOP_MIDI - COMFORTABLE_TOP_MIDI) as f64;
+    [range, leap, rate, height].iter().map(|c| c.clamp(0.0, 1.0)).sum::<f64>() / 4.0
+}
+
+fn correlation(a: &[f64; 12], b: &[f64; 12]) -> f64 {
+    let mean_a = a.iter().sum::<f64>() / 12.0;
+    let mean_b = b.iter().sum::<f64>() / 12.0;
+    let mut cov = 0.0;
+    let mut var_a = 0.0;
+    let mut var_b = 0.0;
+    for (x, y) in a.iter().zip(b) {
+        cov += (x - mean_a) * (y - mean_b);
+        var_a += (x - mean_a).powi(2);
+        var_b += (y - mean_b).powi(2);
+    }
+    if var_a <= 0.0 || var_b <= 0.0 {
+        return 0.0;
+    }
+    cov / (var_a * var_b).sqrt()
+}
+
+/// Infer the key signature (`key_fifths`) of a score from its notes. A
+/// duration-weighted pitch-class histogram is correlated against the
+/// Krumhansl major and minor profiles in all twelve keys; a minor key reports
+/// its relative major's signature. Returns 0 for a score with no notes.
+pub fn infer_key(score: &Score) -> i32 {
+    let mut histogram = [0.0; 12];
+    for note in score.notes.iter().filter(|n| !n.is_rest) {
+        histogram[note.midi.rem_euclid(12) as usize] += note.duration_beats;
+    }
+
+    let mut best: Option<(f64, i32)> = None;
+    for tonic in 0..12 {
+        for (profile, major_tonic) in [(&MAJOR_PROFILE, tonic), (&MINOR_PROFILE, (tonic + 3) % 12)] {
+            let rotated: [f64; 12] = core::array::from_fn(|pc| profile[(pc + 12 - tonic) % 12]);
+            let r = correlation(&histogram, &rotated);
+            if best.is_none_or(|(best_r, _)| r > best_r) {
+                best = Some((r, major_tonic as i32));
+            }
+        }
+    }
+
+    // Fifths from C of the major tonic, in -5..=6 (F# rather than Gb)
+    let fifths = best.map_or(0, |(_, tonic)| (tonic * 7).rem_euclid(12));
+    if fifths > 6 {
+        fifths - 12
+    } else {
+        fifths
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(start_beat: f64, midi: i32) -> NoteEvent {
+        NoteEvent {
+            start_beat,
+            duration_beats: 1.0,
+            midi,
+            is_rest: midi < 0,
+            measure_number: (start_beat / 4.0) as u32 + 1,
+            note_type: "quarter".to_string(),
+            microtone_cents: 0.0,
+            articulations: Vec::new(),
+            beam_group: None,
+            dynamics: None,
+            velocity: None,
+            ornaments: Vec::new(),
+            accidental: None,
+            glissando_to: false,
+            accompaniment: false,
+        }
+    }
+
+    fn score_of(notes: Vec<NoteEvent>) -> Score {
+        let total_beats = notes
+            .iter()
+            .map(|n| n.start_beat + n.duration_beats)
+            .fold(0.0_f64, f64::max);
+        Score {
+            tempo: 120.0,
+            notes,
+            measures: vec![],
+            key_fifths: 0,
+            transpose: None,
+            title: None,
+            total_beats,
+            wedges: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_hardest_interval_octave() {
+        let score = score_of(vec![
+            note(0.0, 60),
+            note(1.0, 64),
+            note(2.0, -1),
+            // Rest is skipped: G4 -> G5 is the octave leap
+            note(3.0, 67),
+            note(4.0, 79),
+            // Chord member on the same beat is ignored
+            note(4.0, 55),
+            note(5.0, 77),
+        ]);
+        assert_eq!(hardest_interval(&score), Some((67, 79, 12)));
+    }
+
+    #[test]
+    fn test_note_density() {
+        let score = score_of(vec![
+            note(0.0, 60),
+            note(1.0, 62),
+            note(2.0, 64),
+            note(3.0, 65),
+            note(4.0, 67),
+            note(5.0, -1),
+            note(6.0, 69),
+            note(7.0, -1),
+        ]);
+        assert_eq!(note_density(&score), vec![(1, 4), (2, 2)]);
+    }
+
+    #[test]
+    fn test_phrase_durations_seconds() {
+        // Two 2-beat phrases split by a rest at 120 bpm
+        let score = score_of(vec![
+            note(0.0, 60),
+            note(1.0, 62),
+            note(2.0, -1),
+            note(3.0, 64),
+            note(4.0, 65),
+        ]);
+        assert_eq!(phrase_durations_seconds(&score), vec![1.0, 1.0]);
+        assert!(phrase_durations_seconds(&score_of(vec![note(0.0, -1)])).is_empty());
+    }
+
+    #[test]
+    fn test_infer_key() {
+        let c_major = score_of(
+            [60, 62, 64, 65, 67, 69, 71, 72]
+                .iter()
+                .enumerate()
+                .map(|(i, &midi)| note(i as f64, midi))
+                .collect(),
+        );
+        assert_eq!(infer_key(&c_major), 0);
+
+        // G major passage: G B D F# G E D B A G
+        let g_major = score_of(
+            [67, 71, 74, 66, 67, 64, 62, 59, 57, 55]
+                .iter()
+                .enumerate()
+                .map(|(i, &midi)| note(i as f64, midi))
+                .collect(),
+        );
+        assert_eq!(infer_key(&g_major), 1);
+        assert_eq!(infer_key(&score_of(vec![])), 0);
+    }
+
+    #[test]
+    fn test_estimate_difficulty() {
+        use crate::exercises::generators::generate_with_options;
+
+        let easy = generate_with_options("long_tones", "C4", 60.0, Some(1), None).unwrap();
+        let hard = generate_with_options("octave_studies", "G4", 160.0, Some(4), None).unwrap();
+        let easy_difficulty = estimate_difficulty(&easy);
+        let hard_difficulty = estimate_difficulty(&hard);
+        assert!(easy_difficulty < hard_difficulty, "{} vs {}", easy_difficulty, hard_difficulty);
+        assert!((0.0..=1.0).contains(&hard_difficulty));
+        assert_eq!(estimate_difficulty(&score_of(vec![])), 0.0);
+    }
+
+    #[test]
+    fn test_hardest_interval_too_few_notes() {
+        assert_eq!(hardest_interval(&score_of(vec![note(0.0, 60)])), None);
+        assert_eq!(hardest_interval(&score_of(vec![])), None);
+    }
+}
@@ -0,0 +1,191 @@
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+#[cfg(not(feature = "std"))]
+use crate::math::FloatMath;
+use crate::scoring::stats::melody;
+use crate::scoring::types::{PlayedNote, Score};
+
+/// Inter-onset intervals are rounded to a multiple of this many beats, so
+/// eighth notes and held notes map onto the grid as well as quarters.
+const QUANTUM_BEATS: f64 = 0.5;
+/// Weight of each new interval's implied beat length in the running
+/// estimate: higher follows tempo changes faster but jitters more.
+const TEMPO_SMOOTHING: f64 = 0.3;
+/// A played note pairs with a target when on its pitch and within this many
+/// beats of where the drift so far puts it.
+const TEMPO_MATCH_WINDOW_BEATS: f64 = 1.0;
+
+/// Streaming beat tracker for free-tempo practice, where the student sets
+/// the pace. Onset times (seconds) arrive one at a time and are mapped to
+/// beats. The first two onsets are taken to be a beat apart, like a
+/// count-in; later intervals are rounded to `QUANTUM_BEATS` at the current
+/// tempo and nudge the tempo towards what they imply.
+#[derive(Clone, Debug, Default)]
+pub struct BeatTracker {
+    /// Current beat length in seconds, once two onsets have been seen.
+    beat_seconds: Option<f64>,
+    /// Time and beat of the most recent onset.
+    last: Option<(f64, f64)>,
+}
+
+impl BeatTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record an onset and return the beat it falls on. Onsets at or
+    /// before the previous one are ignored and return its beat.
+    pub fn push_onset(&mut self, time_seconds: f64) -> f64 {
+        let Some((last_time, last_beat)) = self.last else {
+            self.last = Some((time_seconds, 0.0));
+            return 0.0;
+        };
+        let interval = time_seconds - last_time;
+        if interval <= 0.0 {
+            return last_beat;
+        }
+
+        let beats = match self.beat_seconds {
+            None => {
+                self.beat_seconds = Some(interval);
+                1.0
+            }
+            Some(beat_seconds) => {
+                let beats =
+                    ((interval / beat_seconds / QUANTUM_BEATS).round() * QUANTUM_BEATS)
+                        .max(QUANTUM_BEATS);
+                let implied = interval / beats;
+                self.beat_seconds = Some(beat_seconds + TEMPO_SMOOTHING * (implied - beat_seconds));
+                beats
+            }
+        };
+        let beat = last_beat + beats;
+        self.last = Some((time_seconds, beat));
+        beat
+    }
+
+    /// Current tempo estimate, or `None` before two onsets.
+    pub fn bpm(&self) -> Option<f64> {
+        self.beat_seconds.map(|s| 60.0 / s)
+    }
+
+    /// Beat position (phase included) at `time_seconds`, extrapolated from
+    /// the last onset at the current tempo. `None` before two onsets.
+    pub fn beat_at(&self, time_seconds: f64) -> Option<f64> {
+        let (last_time, last_beat) = self.last?;
+        Some(last_beat + (time_seconds - last_time) / self.beat_seconds?)
+    }
+}
+
+/// Local tempo of each measure as a signed percentage off the score tempo
+/// (-25.0 is a quarter slower), for spotting where a take slows or rushes.
+/// Targets are paired in order with played notes on the same pitch, which
+/// follows the player through gradual drift. A measure's tempo comes from
+/// the span from its first paired onset to the next measure's first (or its
+/// own last); measures with too few pairs are left out.
+pub fn tempo_stability(score: &Score, played: &[PlayedNote]) -> Vec<(u32, f64)> {
+    let mut sorted: Vec<&PlayedNote> = played.iter().collect();
+    sorted.sort_by(|a, b| a.onset_beat.partial_cmp(&b.onset_beat).unwrap_or(Ordering::Equal));
+
+    // (measure, target beat, played beat) for each paired target
+    let mut pairs: Vec<(u32, f64, f64)> = Vec::new();
+    let mut next = 0;
+    let mut drift = 0.0;
+    for target in melody(score) {
+        let expected = target.start_beat + drift;
+        let found = sorted[next..].iter().position(|p| {
+            p.midi_rounded == target.midi
+                && (p.onset_beat - expected).abs() <= TEMPO_MATCH_WINDOW_BEATS
+        });
+        if let Some(i) = found {
+            let onset = sorted[next + i].onset_beat;
+            drift = onset - target.start_beat;
+            pairs.push((target.measure_number, target.start_beat, onset));
+            next += i + 1;
+        }
+    }
+
+    let mut stability = Vec::new();
+    let mut first = 0;
+    while first < pairs.len() {
+        let measure = pairs[first].0;
+        let last = first + pairs[first..].iter().take_while(|p| p.0 == measure).count() - 1;
+        let end = pairs.get(last + 1).unwrap_or(&pairs[last]);
+        let (_, target_start, played_start) = pairs[first];
+        let score_span = end.1 - target_start;
+        let played_span = end.2 - played_start;
+        if score_span > 0.0 && played_span > 0.0 {
+            stability.push((measure, (score_span / played_span - 1.0) * 100.0));
+        }
+        first = last + 1;
+    }
+    stability
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exercises::generators::generate_pattern;
+
+    #[test]
+    fn test_beat_tracker_steady_onsets() {
+        // Quarter notes at 90 bpm, starting 1.2 s into the recording
+        let mut tracker = BeatTracker::new();
+        assert_eq!(tracker.bpm(), None);
+        for i in 0..16 {
+            let beat = tracker.push_onset(1.2 + i as f64 * 60.0 / 90.0);
+            assert!((beat - i as f64).abs() < 1e-9, "onset {} at beat {}", i, beat);
+            if i > 0 {
+                assert!((tracker.bpm().unwrap() - 90.0).abs() < 1e-6);
+            }
+        }
+        // Halfway to the next onset
+        let half = 1.2 + 15.5 * 60.0 / 90.0;
+        assert!((tracker.beat_at(half).unwrap() - 15.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_beat_tracker_subdivisions_and_drift() {
+        let mut tracker = BeatTracker::new();
+        tracker.push_onset(0.0);
+        tracker.push_onset(0.5);
+        // Two eighths, then a half note
+        assert_eq!(tracker.push_onset(0.75), 1.5);
+        assert_eq!(tracker.push_onset(1.0), 2.0);
+        assert_eq!(tracker.push_onset(2.0), 4.0);
+        // The student slows to 100 bpm; the estimate follows
+        let mut time = 2.0;
+        for _ in 0..20 {
+            time += 0.6;
+            tracker.push_onset(time);
+        }
+        assert!((tracker.bpm().unwrap() - 100.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_tempo_stability_flags_slow_measure() {
+        // Three measures of quarter notes, the second dragged to 2/3 speed
+        let score = generate_pattern(60, 120.0, &[0, 2, 4], 3, false);
+        let onsets = [0.0, 1.0, 2.0, 4.0, 5.5, 7.0, 10.0, 11.0, 12.0];
+        let played: Vec<PlayedNote> = score
+            .notes
+            .iter()
+            .filter(|n| !n.is_rest)
+            .zip(onsets)
+            .map(|(n, onset_beat)| PlayedNote {
+                onset_beat,
+                midi_float: n.midi as f64,
+                midi_rounded: n.midi,
+                confidence: 0.9,
+            })
+            .collect();
+
+        let stability = tempo_stability(&score, &played);
+        let measures: Vec<u32> = stability.iter().map(|s| s.0).collect();
+        assert_eq!(measures, vec![1, 2, 3]);
+        assert!(stability[0].1.abs() < 1e-9);
+        assert!((stability[1].1 + 100.0 / 3.0).abs() < 1e-9, "{:?}", stability);
+        assert!(stability[2].1.abs() < 1e-9);
+    }
+}
@@ -1,6 +1,11 @@
+#[cfg(feature = "binary")]
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
 pub struct NoteEvent {
     pub start_beat: f64,
     pub duration_beats: f64,
@@ -8,11 +13,57 @@ pub struct NoteEvent {
     pub is_rest: bool,
     pub measure_number: u32,
     pub note_type: String,
+    /// Microtonal offset from `midi` in cents (e.g. -50 for a quarter-tone
+    /// sharp notated as `<alter>0.5</alter>`, which rounds `midi` up).
+    #[serde(default)]
+    pub microtone_cents: f64,
+    /// Articulation marks, e.g. "staccato", "accent", "tenuto".
+    #[serde(default)]
+    pub articulations: Vec<String>,
+    /// Primary beam group id; consecutive beamed notes share an id.
+    #[serde(default)]
+    pub beam_group: Option<u32>,
+    /// Dynamic in effect: a marking such as "mf" (accent-type marks like
+    /// "sfz" or "fp" apply to a single note), or a swell shape such as
+    /// "pp<ff>pp" (crescendo to ff, then back) for generated exercises.
+    #[serde(default)]
+    pub dynamics: Option<String>,
+    /// MIDI playback velocity (1-127) from a `<sound dynamics>` attribute.
+    #[serde(default)]
+    pub velocity: Option<u8>,
+    /// Ornament marks, e.g. "trill-mark", "mordent", "turn".
+    #[serde(default)]
+    pub ornaments: Vec<String>,
+    /// Accidental as written for display, e.g. "natural", "sharp", "flat",
+    /// including courtesy accidentals. Pitch comes from `midi` alone.
+    #[serde(default)]
+    pub accidental: Option<String>,
+    /// Slides (glissando or slide) into the next note, so the end of this
+    /// note's trail is a sweep rather than a held pitch.
+    #[serde(default)]
+    pub glissando_to: bool,
+    /// Sounded by something other than the player (e.g. a generated drone),
+    /// so it is never a target to be graded.
+    #[serde(default)]
+    pub accompaniment: bool,
+}
+
+impl NoteEvent {
+    /// Exact notated pitch as fractional MIDI, including any microtonal offset.
+    pub fn target_pitch(&self) -> f64 {
+        self.midi as f64 + self.microtone_cents / 100.0
+    }
+
+    /// Whether the player sounds this note: neither a rest nor accompaniment.
+    pub fn is_played(&self) -> bool {
+        !self.is_rest && !self.accompaniment
+    }
 }
 
 // Performance tracking types
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
 pub struct PlayedNote {
     pub onset_beat: f64,
     pub midi_float: f64,
@@ -21,6 +72,7 @@ pub struct PlayedNote {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
 pub struct NoteResult {
     pub target_midi: i32,
     pub target_beat: f64,
@@ -28,24 +80,53 @@ pub struct NoteResult {
     pub played_midi: Option<f64>,
     pub pitch_error_cents: Option<f64>,
     pub timing_error_beats: Option<f64>,
+    // Graded target and played pitch in Hz at the analysis reference pitch
+    #[serde(default)]
+    pub target_hz: f64,
+    #[serde(default)]
+    pub played_hz: Option<f64>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
 pub struct PitchTrailPoint {
     pub beat: f64,
     pub midi_float: f64,
 }
 
+/// A note played where the score has a rest: the rest's span and the
+/// offending played note.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct RestViolation {
+    pub rest_start_beat: f64,
+    pub rest_end_beat: f64,
+    pub played: PlayedNote,
+}
+
+/// Loudness sample at the pitch trail's cadence, for correlating dynamics
+/// with pitch.
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct DynamicsTrailPoint {
+    pub beat: f64,
+    pub rms: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
 pub struct IntervalProblem {
     pub from_note: String,
     pub to_note: String,
     pub direction: String, // "up" or "down"
     pub avg_error_cents: f64,
+    #[serde(default)]
+    pub avg_timing_error_beats: f64, // positive = late
     pub count: u32,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
 pub struct PerformanceAnalysis {
     pub total_notes: u32,
     pub notes_correct: u32,
@@ -56,6 +137,9 @@ pub struct PerformanceAnalysis {
     pub pitch_tendency: String,  // "sharp", "flat", "accurate"
     pub timing_tendency: String, // "early", "late", "on_time"
     pub problem_intervals: Vec<IntervalProblem>,
+    // Intervals after which the entry is consistently late or early
+    #[serde(default)]
+    pub timing_problem_intervals: Vec<IntervalProblem>,
     pub feedback: Vec<String>,
     pub overall_score: f64, // 0-100
     pub note_results: Vec<NoteResult>,
@@ -64,25 +148,176 @@ pub struct PerformanceAnalysis {
     pub attack_quality: Option<f64>,   // 0-1 score, how quickly pitch stabilizes
     pub breath_support: Option<f64>,   // 0-1 score, pitch sustain consistency
     pub endurance_delta: Option<f64>,  // accuracy drop: first half vs second half
+    // Signed cents where the steady state (after the attack) sits vs target
+    #[serde(default)]
+    pub centering_cents: Option<f64>,
+    // A high note was both sharp and unsteady, the sound of forcing
+    #[serde(default)]
+    pub forcing_detected: bool,
     pub technique_feedback: Vec<String>,
+    // Articulation analysis (populated when pitch_trail is provided)
+    #[serde(default)]
+    pub articulation_feedback: Vec<String>,
+    // Extra attempts at an already-played target (sight-reading mode)
+    #[serde(default)]
+    pub reattempts: u32,
+    // Opened rushing or dragging but was back on the beat by the end
+    #[serde(default)]
+    pub tempo_recovered: bool,
+    // Detected notes, matched or not: zero means nothing was played
+    #[serde(default)]
+    pub played_notes_count: u32,
+    // Fraction of the score's sounding beats where no pitch was detected
+    #[serde(default)]
+    pub silence_ratio: f64,
+}
+
+/// Encode an analysis in the compact postcard binary format, for storing
+/// many sessions where JSON would be too bulky.
+#[cfg(feature = "binary")]
+pub fn analysis_to_bytes(analysis: &PerformanceAnalysis) -> Vec<u8> {
+    postcard::to_allocvec(analysis).expect("PerformanceAnalysis always serializes")
+}
+
+/// Decode an analysis written by `analysis_to_bytes`.
+#[cfg(feature = "binary")]
+pub fn analysis_from_bytes(bytes: &[u8]) -> Result<PerformanceAnalysis, String> {
+    postcard::from_bytes(bytes).map_err(|e| format!("Invalid analysis bytes: {}", e))
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct ArticulationReport {
+    pub notes_per_second: f64,
+    pub mean_interval_seconds: f64,
+    pub interval_std_dev_seconds: f64, // unevenness: lower is more even
+}
+
+/// Reference tuning that played pitches are graded against.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "camel-case", serde(rename_all_fields = "camelCase"))]
+pub enum TuningSystem {
+    /// Twelve-tone equal temperament: the notated MIDI pitch.
+    #[default]
+    Equal,
+    /// 5-limit just intonation relative to the tonic's pitch class.
+    Just { tonic_midi: i32 },
+}
+
+impl TuningSystem {
+    /// Cents offset of `midi` from equal temperament in this tuning.
+    pub fn offset_cents(&self, midi: i32) -> f64 {
+        // Deviation of 1/1, 16/15, 9/8, 6/5, 5/4, 4/3, 45/32, 3/2, 8/5, 5/3,
+        // 9/5, 15/8 from the equal-tempered degrees
+        const JUST_CENTS: [f64; 12] = [
+            0.0, 11.73, 3.91, 15.64, -13.69, -1.96, -9.78, 1.96, 13.69, -15.64, 17.6, -11.73,
+        ];
+        match self {
+            TuningSystem::Equal => 0.0,
+            TuningSystem::Just { tonic_midi } => JUST_CENTS[(midi - tonic_midi).rem_euclid(12) as usize],
+        }
+    }
+}
+
+/// Optional analyzer settings. Defaults reproduce `analyze_performance`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+#[serde(default)]
+pub struct AnalysisOptions {
+    /// Match and grade microtonal targets against their nearest semitone.
+    pub snap_microtones: bool,
+    /// Weight timing errors by metric strength so downbeats count more.
+    pub weight_timing_by_beat: bool,
+    /// Points subtracted from `overall_score` per unmatched note played
+    /// during a notated rest.
+    pub rest_penalty: f64,
+    /// Number of opening target notes treated as a settling period and
+    /// excluded from counts and averages.
+    pub warmup_notes: usize,
+    /// Tuning the played pitch is graded against.
+    pub tuning: TuningSystem,
+    /// Sight-reading mode: a looser timing window, but only the first
+    /// played note near a target counts; later ones are re-attempts.
+    pub sight_reading: bool,
+    /// A4 reference in Hz for the frequencies in `NoteResult` (e.g. from
+    /// `calibrate_reference`). `None` means 440 Hz.
+    pub reference_hz: Option<f64>,
+    /// Detector delay in beats (e.g. half the YIN window), subtracted from
+    /// each `onset_beat` before matching.
+    pub latency_beats: f64,
+    /// Pitch-only mode: played notes are matched to targets purely in
+    /// order, ignoring timing, and no timing errors are reported.
+    pub pitch_only: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct Clef {
+    pub sign: String, // "G", "F", "C", "percussion"
+    pub line: u8,
+    pub octave_change: i32, // e.g. -1 for treble-8vb
+}
+
+impl Clef {
+    pub fn treble() -> Self {
+        Clef {
+            sign: "G".to_string(),
+            line: 2,
+            octave_change: 0,
+        }
+    }
+}
+
+impl Default for Clef {
+    fn default() -> Self {
+        Clef::treble()
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
 pub struct MeasureInfo {
     pub number: u32,
     pub start_beat: f64,
     pub duration_beats: f64,
     pub time_sig_num: u8,
     pub time_sig_den: u8,
+    #[serde(default)]
+    pub clef: Clef,
+    /// Senza misura (cadenza) measure, or one before the part's first time
+    /// signature: no metric timing is enforced.
+    #[serde(default)]
+    pub unmetered: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
 pub struct TransposeInfo {
     pub chromatic: i32,
     pub diatonic: i32,
 }
 
+impl TransposeInfo {
+    /// Bb trumpet: written C sounds a major second lower.
+    pub fn bb_trumpet() -> Self {
+        TransposeInfo {
+            chromatic: -2,
+            diatonic: -1,
+        }
+    }
+}
+
+/// A crescendo or diminuendo hairpin, from its start to its stop.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct WedgeSpan {
+    pub start_beat: f64,
+    pub end_beat: f64,
+    pub direction: String, // "crescendo" or "diminuendo"
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
 pub struct Score {
     pub tempo: f64,
     pub notes: Vec<NoteEvent>,
@@ -91,4 +326,256 @@ pub struct Score {
     pub transpose: Option<TransposeInfo>,
     pub title: Option<String>,
     pub total_beats: f64,
+    #[serde(default)]
+    pub wedges: Vec<WedgeSpan>,
+}
+
+impl Score {
+    /// Lowest and highest MIDI pitch among played notes, or `None` if the
+    /// score has no sounding notes.
+    pub fn midi_range(&self) -> Option<(i32, i32)> {
+        let mut pitches = self.notes.iter().filter(|n| n.is_played()).map(|n| n.midi);
+        let first = pitches.next()?;
+        Some(pitches.fold((first, first), |(lo, hi), m| (lo.min(m), hi.max(m))))
+    }
+
+    /// Sorted, unique MIDI pitches of the played notes, e.g. for a "notes
+    /// in this piece" chart built with `partials::fingering_for_midi`.
+    pub fn distinct_pitches(&self) -> Vec<i32> {
+        let mut pitches: Vec<i32> =
+            self.notes.iter().filter(|n| n.is_played()).map(|n| n.midi).collect();
+        pitches.sort_unstable();
+        pitches.dedup();
+        pitches
+    }
+
+    /// First played note starting at or after `beat`, for a live
+    /// "up next" cursor.
+    pub fn next_note_at(&self, beat: f64) -> Option<&NoteEvent> {
+        self.notes
+            .iter()
+            .find(|n| n.is_played() && n.start_beat >= beat)
+    }
+
+    /// Key signature the part sounds in at concert pitch, in fifths: the
+    /// written `key_fifths` moved by the transposition (a Bb trumpet's
+    /// written C major sounds in Bb, -2). Untransposed scores return
+    /// `key_fifths` unchanged.
+    pub fn concert_key_fifths(&self) -> i32 {
+        let Some(transpose) = &self.transpose else {
+            return self.key_fifths;
+        };
+        // Each semitone is 7 fifths around the circle, mod 12
+        let shift = (transpose.chromatic * 7).rem_euclid(12);
+        let shift = if shift > 6 { shift - 12 } else { shift };
+        let fifths = self.key_fifths + shift;
+        if fifths > 7 {
+            fifths - 12
+        } else if fifths < -7 {
+            fifths + 12
+        } else {
+            fifths
+        }
+    }
+
+    /// Measure whose `[start_beat, start_beat + duration_beats)` span
+    /// contains `beat`, so a beat on a barline belongs to the measure it
+    /// starts. `None` before the first measure or past the last.
+    pub fn measure_at(&self, beat: f64) -> Option<&MeasureInfo> {
+        self.measures
+            .iter()
+            .find(|m| beat >= m.start_beat && beat < m.start_beat + m.duration_beats)
+    }
+
+    /// Measures `first..=last` as a standalone score with beats rebased so
+    /// the first measure starts at beat 0. Measure numbers are kept.
+    pub fn slice_measures(&self, first: u32, last: u32) -> Score {
+        let in_range = |number: u32| number >= first && number <= last;
+        let measures: Vec<MeasureInfo> = self
+            .measures
+            .iter()
+            .filter(|m| in_range(m.number))
+            .cloned()
+            .collect();
+        let mut notes: Vec<NoteEvent> = self
+            .notes
+            .iter()
+            .filter(|n| in_range(n.measure_number))
+            .cloned()
+            .collect();
+
+        let offset = measures
+            .first()
+            .map(|m| m.start_beat)
+            .or_else(|| notes.first().map(|n| n.start_beat))
+            .unwrap_or(0.0);
+        let end = measures
+            .iter()
+            .map(|m| m.start_beat + m.duration_beats)
+            .chain(notes.iter().map(|n| n.start_beat + n.duration_beats))
+            .fold(offset, f64::max);
+
+        for note in &mut notes {
+            note.start_beat -= offset;
+        }
+        // Hairpins overlapping the slice, cut at its edges
+        let wedges = self
+            .wedges
+            .iter()
+            .filter(|w| w.end_beat > offset && w.start_beat < end)
+            .map(|w| WedgeSpan {
+                start_beat: w.start_beat.max(offset) - offset,
+                end_beat: w.end_beat.min(end) - offset,
+                direction: w.direction.clone(),
+            })
+            .collect();
+        Score {
+            tempo: self.tempo,
+            notes,
+            measures: measures
+                .into_iter()
+                .map(|m| MeasureInfo {
+                    start_beat: m.start_beat - offset,
+                    ..m
+                })
+                .collect(),
+            key_fifths: self.key_fifths,
+            transpose: self.transpose.clone(),
+            title: self.title.clone(),
+            total_beats: end - offset,
+            wedges,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exercises::generators::{generate, generate_with_options};
+
+    #[test]
+    fn test_midi_range_major_scale() {
+        let score = generate("major_scale", "C4", 120.0).unwrap();
+        assert_eq!(score.midi_range(), Some((60, 72)));
+    }
+
+    #[test]
+    fn test_distinct_pitches_major_scale() {
+        let score = generate("major_scale", "C4", 120.0).unwrap();
+        let pitches = score.distinct_pitches();
+        assert_eq!(pitches, vec![60, 62, 64, 65, 67, 69, 71, 72]);
+        assert!(pitches.iter().all(|&m| crate::partials::fingering_for_midi(m).is_some()));
+    }
+
+    #[test]
+    fn test_next_note_at() {
+        // Quarter-note C major scale (difficulty 3)
+        let score = generate_with_options("major_scale", "C4", 120.0, Some(3), None).unwrap();
+        assert_eq!(score.next_note_at(0.0).map(|n| n.midi), Some(60));
+        // Mid-measure, between beats 2 and 3: next is F4 on beat 3
+        assert_eq!(score.next_note_at(2.5).map(|n| n.midi), Some(65));
+        assert_eq!(score.next_note_at(3.0).map(|n| n.midi), Some(65));
+        assert!(score.next_note_at(score.total_beats + 1.0).is_none());
+    }
+
+    #[test]
+    fn test_concert_key_fifths_bb_trumpet() {
+        let mut score = generate("long_tones", "C4", 60.0).unwrap();
+        assert_eq!(score.concert_key_fifths(), 0);
+
+        score.transpose = Some(TransposeInfo {
+            chromatic: -2,
+            diatonic: -1,
+        });
+        assert_eq!(score.concert_key_fifths(), -2);
+        // Written D major (2 sharps) sounds in concert C
+        score.key_fifths = 2;
+        assert_eq!(score.concert_key_fifths(), 0);
+        // Written Gb (6 flats) sounds in Fb, spelled as E (4 sharps)
+        score.key_fifths = -6;
+        assert_eq!(score.concert_key_fifths(), 4);
+    }
+
+    #[test]
+    fn test_measure_at_boundaries() {
+        let score = generate("major_scale", "C4", 120.0).unwrap();
+        assert_eq!(score.measure_at(0.0).map(|m| m.number), Some(1));
+        assert_eq!(score.measure_at(3.99).map(|m| m.number), Some(1));
+        assert_eq!(score.measure_at(4.0).map(|m| m.number), Some(2));
+        assert!(score.measure_at(-0.5).is_none());
+        let end = score.measures.last().map(|m| m.start_beat + m.duration_beats).unwrap();
+        assert!(score.measure_at(end).is_none());
+    }
+
+    #[test]
+    fn test_midi_range_all_rests() {
+        let mut score = generate("major_scale", "C4", 120.0).unwrap();
+        for note in &mut score.notes {
+            note.is_rest = true;
+        }
+        assert_eq!(score.midi_range(), None);
+        score.notes.clear();
+        assert_eq!(score.midi_range(), None);
+    }
+
+    fn played_json() -> serde_json::Value {
+        serde_json::to_value(PlayedNote {
+            onset_beat: 1.0,
+            midi_float: 60.1,
+            midi_rounded: 60,
+            confidence: 0.9,
+        })
+        .unwrap()
+    }
+
+    #[cfg(feature = "binary")]
+    #[test]
+    fn test_analysis_bytes_round_trip() {
+        use crate::scoring::analyzer::analyze_performance;
+
+        let score = generate("major_scale", "C4", 120.0).unwrap();
+        let played: Vec<PlayedNote> = score
+            .notes
+            .iter()
+            .map(|n| PlayedNote {
+                onset_beat: n.start_beat + 0.05,
+                midi_float: n.midi as f64 + 0.1,
+                midi_rounded: n.midi,
+                confidence: 0.9,
+            })
+            .collect();
+        let analysis = analyze_performance(&score, &played, 50.0, 0.3);
+
+        let bytes = analysis_to_bytes(&analysis);
+        assert!(bytes.len() < serde_json::to_vec(&analysis).unwrap().len());
+        let decoded = analysis_from_bytes(&bytes).unwrap();
+        assert_eq!(
+            serde_json::to_value(&decoded).unwrap(),
+            serde_json::to_value(&analysis).unwrap()
+        );
+        assert!(analysis_from_bytes(&bytes[..bytes.len() / 2]).is_err());
+    }
+
+    #[cfg(feature = "camel-case")]
+    #[test]
+    fn test_serialize_camel_case() {
+        let json = played_json();
+        assert!(json.get("onsetBeat").is_some());
+        assert!(json.get("midiFloat").is_some());
+        assert!(json.get("onset_beat").is_none());
+
+        let score = serde_json::to_value(generate("long_tones", "C4", 60.0).unwrap()).unwrap();
+        assert!(score["notes"][0].get("startBeat").is_some());
+        assert!(score.get("totalBeats").is_some());
+        let tuning = serde_json::to_value(TuningSystem::Just { tonic_midi: 60 }).unwrap();
+        assert_eq!(tuning["Just"]["tonicMidi"], 60);
+    }
+
+    #[cfg(not(feature = "camel-case"))]
+    #[test]
+    fn test_serialize_snake_case() {
+        let json = played_json();
+        assert!(json.get("onset_beat").is_some());
+        assert!(json.get("midi_float").is_some());
+    }
 }
@@ -0,0 +1,223 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::scoring::types::*;
+
+/// Beat differences below this are treated as rounding noise.
+const BEAT_EPSILON: f64 = 1e-6;
+
+/// Most differences `score_diff` reports before stopping.
+const MAX_SCORE_DIFFS: usize = 20;
+
+/// Check that consecutive notes (rests included, chord members skipped)
+/// follow each other without gaps or overlaps, and that no note runs past
+/// the end of its measure. Returns one warning per problem found.
+pub fn validate_timeline(score: &Score) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let mut prev: Option<&NoteEvent> = None;
+    for note in &score.notes {
+        if let Some(p) = prev {
+            if note.start_beat == p.start_beat && !note.is_rest && !p.is_rest {
+                continue; // chord member
+            }
+            let expected = p.start_beat + p.duration_beats;
+            let delta = note.start_beat - expected;
+            if delta < -BEAT_EPSILON {
+                warnings.push(format!(
+                    "Overlap at beat {}: note starts {} beats before the previous note ends",
+                    note.start_beat, -delta
+                ));
+            } else if delta > BEAT_EPSILON {
+                warnings.push(format!(
+                    "Gap at beat {}: {} beats missing before this note",
+                    expected, delta
+                ));
+            }
+        }
+        prev = Some(note);
+    }
+
+    for note in &score.notes {
+        let Some(measure) = score.measures.iter().find(|m| m.number == note.measure_number) else {
+            continue;
+        };
+        let measure_end = measure.start_beat + measure.duration_beats;
+        if note.start_beat + note.duration_beats > measure_end + BEAT_EPSILON {
+            warnings.push(format!(
+                "Note at beat {} runs past the end of measure {} (beat {})",
+                note.start_beat, measure.number, measure_end
+            ));
+        }
+    }
+    warnings
+}
+
+/// Differences between two scores, for checking that a score survives a
+/// MusicXML round trip: metadata first, then measures and notes compared by
+/// index. Stops after the first `MAX_SCORE_DIFFS` differences; an empty
+/// list means the scores match.
+pub fn score_diff(a: &Score, b: &Score) -> Vec<String> {
+    let beats_differ = |x: f64, y: f64| (x - y).abs() > BEAT_EPSILON;
+    let mut diffs = Vec::new();
+
+    if beats_differ(a.tempo, b.tempo) {
+        diffs.push(format!("Tempo: {} vs {}", a.tempo, b.tempo));
+    }
+    if a.key_fifths != b.key_fifths {
+        diffs.push(format!("Key: {} vs {} fifths", a.key_fifths, b.key_fifths));
+    }
+    if a.title != b.title {
+        diffs.push(format!("Title: {:?} vs {:?}", a.title, b.title));
+    }
+    let chromatic = |s: &Score| s.transpose.as_ref().map(|t| t.chromatic);
+    if chromatic(a) != chromatic(b) {
+        diffs.push(format!("Transpose: {:?} vs {:?} semitones", chromatic(a), chromatic(b)));
+    }
+    if beats_differ(a.total_beats, b.total_beats) {
+        diffs.push(format!("Total beats: {} vs {}", a.total_beats, b.total_beats));
+    }
+
+    if a.measures.len() != b.measures.len() {
+        diffs.push(format!("Measure count: {} vs {}", a.measures.len(), b.measures.len()));
+    }
+    for (ma, mb) in a.measures.iter().zip(&b.measures) {
+        if beats_differ(ma.start_beat, mb.start_beat)
+            || beats_differ(ma.duration_beats, mb.duration_beats)
+        {
+            diffs.push(format!(
+                "Measure {}: beats {}+{} vs {}+{}",
+                ma.number, ma.start_beat, ma.duration_beats, mb.start_beat, mb.duration_beats
+            ));
+        }
+        if (ma.time_sig_num, ma.time_sig_den) != (mb.time_sig_num, mb.time_sig_den) {
+            diffs.push(format!(
+                "Measure {}: time signature {}/{} vs {}/{}",
+                ma.number, ma.time_sig_num, ma.time_sig_den, mb.time_sig_num, mb.time_sig_den
+            ));
+        }
+    }
+
+    if a.notes.len() != b.notes.len() {
+        diffs.push(format!("Note count: {} vs {}", a.notes.len(), b.notes.len()));
+    }
+    for (i, (na, nb)) in a.notes.iter().zip(&b.notes).enumerate() {
+        if na.is_rest != nb.is_rest || (!na.is_rest && na.midi != nb.midi) {
+            let pitch = |n: &NoteEvent| {
+                if n.is_rest {
+                    String::from("rest")
+                } else {
+                    format!("{}", n.midi)
+                }
+            };
+            diffs.push(format!("Note {}: pitch {} vs {}", i, pitch(na), pitch(nb)));
+        }
+        if beats_differ(na.start_beat, nb.start_beat) {
+            diffs.push(format!("Note {}: start beat {} vs {}", i, na.start_beat, nb.start_beat));
+        }
+        if beats_differ(na.duration_beats, nb.duration_beats) {
+            diffs.push(format!(
+                "Note {}: duration {} vs {} beats",
+                i, na.duration_beats, nb.duration_beats
+            ));
+        }
+        if diffs.len() >= MAX_SCORE_DIFFS {
+            break;
+        }
+    }
+    diffs.truncate(MAX_SCORE_DIFFS);
+    diffs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exercises::generators::generate;
+
+    fn note(start_beat: f64, duration_beats: f64, midi: i32) -> NoteEvent {
+        NoteEvent {
+            start_beat,
+            duration_beats,
+            midi,
+            is_rest: midi < 0,
+            measure_number: 1,
+            note_type: "quarter".to_string(),
+            microtone_cents: 0.0,
+            articulations: Vec::new(),
+            beam_group: None,
+            dynamics: None,
+            velocity: None,
+            ornaments: Vec::new(),
+            accidental: None,
+            glissando_to: false,
+            accompaniment: false,
+        }
+    }
+
+    fn score_of(notes: Vec<NoteEvent>) -> Score {
+        Score {
+            tempo: 120.0,
+            notes,
+            measures: vec![MeasureInfo {
+                number: 1,
+                start_beat: 0.0,
+                duration_beats: 4.0,
+                time_sig_num: 4,
+                time_sig_den: 4,
+                clef: Clef::treble(),
+                unmetered: false,
+            }],
+            key_fifths: 0,
+            transpose: None,
+            title: None,
+            total_beats: 4.0,
+            wedges: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_validate_timeline_overlap() {
+        // The half note on beat 1 overlaps the note on beat 2
+        let score = score_of(vec![
+            note(0.0, 1.0, 60),
+            note(1.0, 2.0, 62),
+            note(2.0, 3.0, 64),
+        ]);
+        let warnings = validate_timeline(&score);
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].starts_with("Overlap at beat 2"));
+        assert!(warnings[1].contains("past the end of measure 1"));
+    }
+
+    #[test]
+    fn test_validate_timeline_clean() {
+        let score = score_of(vec![
+            note(0.0, 1.0, 60),
+            // Chord member shares the onset
+            note(0.0, 1.0, 64),
+            note(1.0, 1.0, -1),
+            note(2.0, 2.0, 67),
+        ]);
+        assert!(validate_timeline(&score).is_empty());
+        assert!(validate_timeline(&generate("long_tones", "C4", 120.0).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn test_score_diff_single_pitch() {
+        let a = generate("major_scale", "C4", 120.0).unwrap();
+        assert!(score_diff(&a, &a.clone()).is_empty());
+
+        let mut b = a.clone();
+        b.notes[2].midi += 1;
+        assert_eq!(score_diff(&a, &b), vec!["Note 2: pitch 64 vs 65".to_string()]);
+    }
+
+    #[test]
+    fn test_validate_timeline_gap() {
+        let score = score_of(vec![note(0.0, 1.0, 60), note(2.0, 1.0, 62)]);
+        assert_eq!(
+            validate_timeline(&score),
+            vec!["Gap at beat 1: 1 beats missing before this note".to_string()]
+        );
+    }
+}
@@ -1,4 +1,9 @@
-use crate::scoring::types::TransposeInfo;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+#[cfg(not(feature = "std"))]
+use crate::math::FloatMath;
+use crate::scoring::types::{Score, TransposeInfo};
 
 /// Convert a concert-pitch MIDI note to written pitch for the instrument.
 /// For Bb trumpet: chromatic = -2, so written C4 (60) sounds as concert Bb3 (58).
@@ -20,15 +25,74 @@ pub fn freq_to_written_midi(freq_hz: f64, transpose: &TransposeInfo) -> f64 {
     concert_midi - transpose.chromatic as f64
 }
 
+/// The score in the same pitch space as the played notes it will be scored
+/// against, the single place transposition is applied before analysis.
+///
+/// Parsed scores are in written pitch, with `transpose` describing the
+/// instrument. If the mic captures concert pitch (`mic_is_concert`, the
+/// usual case for raw `detect_pitch` output), sounding notes and the key are
+/// moved to concert pitch and `transpose` is cleared, so preparing an
+/// already-prepared score changes nothing. If played notes are already in
+/// written pitch (e.g. via `freq_to_written_midi`), or the score has no
+/// transposition, the score is returned unchanged.
+pub fn prepare_for_scoring(score: &Score, mic_is_concert: bool) -> Score {
+    let mut prepared = score.clone();
+    let Some(transpose) = score.transpose.as_ref().filter(|_| mic_is_concert) else {
+        return prepared;
+    };
+    for note in prepared.notes.iter_mut().filter(|n| !n.is_rest) {
+        note.midi = written_to_concert(note.midi, transpose);
+    }
+    prepared.key_fifths = score.concert_key_fifths();
+    prepared.transpose = None;
+    prepared
+}
+
+/// Transposing instruments recognized by `transpose_for_instrument`:
+/// (instrument words, key, chromatic, diatonic). An empty key matches any name.
+const INSTRUMENT_TRANSPOSES: [(&[&str], &str, i32, i32); 9] = [
+    (&["trumpet", "tpt", "trp", "cornet", "flugelhorn"], "bb", -2, -1),
+    (&["trumpet", "tpt", "trp"], "c", 0, 0),
+    (&["trumpet", "tpt", "trp"], "d", 2, 1),
+    (&["trumpet", "tpt", "trp", "cornet"], "eb", 3, 2),
+    (&["trumpet", "tpt", "trp"], "a", -3, -2),
+    (&["clarinet", "cl"], "bb", -2, -1),
+    (&["clarinet", "cl"], "a", -3, -2),
+    (&["horn", "hn"], "f", -7, -4),
+    (&["flugelhorn"], "", -2, -1),
+];
+
+/// Transposition implied by an instrument or part name such as "Trumpet in
+/// Bb", "Bb Tpt." or "Horn in F". Names must state the key (flugelhorn
+/// excepted); a bare "Trumpet" returns `None`.
+pub fn transpose_for_instrument(name: &str) -> Option<TransposeInfo> {
+    let normalized: String = name
+        .to_lowercase()
+        .replace('\u{266d}', "b")
+        .replace("-flat", "b")
+        .replace(" flat", "b");
+    let words: Vec<&str> = normalized
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+
+    INSTRUMENT_TRANSPOSES
+        .iter()
+        .find(|(instruments, key, _, _)| {
+            words.iter().any(|w| instruments.contains(w)) && (key.is_empty() || words.contains(key))
+        })
+        .map(|&(_, _, chromatic, diatonic)| TransposeInfo {
+            chromatic,
+            diatonic,
+        })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     fn bb_trumpet() -> TransposeInfo {
-        TransposeInfo {
-            chromatic: -2,
-            diatonic: -1,
-        }
+        TransposeInfo::bb_trumpet()
     }
 
     #[test]
@@ -74,4 +138,45 @@ mod tests {
         let written = freq_to_written_midi(440.0, &t);
         assert!((written - 71.0).abs() < 0.01);
     }
+
+    #[test]
+    fn test_prepare_for_scoring() {
+        use crate::exercises::generators::generate;
+
+        let mut score = generate("major_scale", "C4", 120.0).unwrap();
+        score.transpose = Some(bb_trumpet());
+        let written: Vec<i32> = score.notes.iter().map(|n| n.midi).collect();
+
+        // Concert-pitch mic: written C4 is heard as Bb3
+        let concert = prepare_for_scoring(&score, true);
+        assert_eq!(concert.notes[0].midi, 58);
+        assert!(concert.notes.iter().zip(&written).all(|(n, w)| n.midi == w - 2));
+        assert_eq!(concert.key_fifths, -2);
+        assert!(concert.transpose.is_none());
+        // Idempotent
+        let again = prepare_for_scoring(&concert, true);
+        assert_eq!(again.notes[0].midi, 58);
+
+        // Played notes already in written pitch: nothing changes
+        let as_written = prepare_for_scoring(&score, false);
+        let midis: Vec<i32> = as_written.notes.iter().map(|n| n.midi).collect();
+        assert_eq!(midis, written);
+        assert_eq!(as_written.key_fifths, 0);
+        assert!(as_written.transpose.is_some());
+    }
+
+    #[test]
+    fn test_transpose_for_instrument() {
+        let chromatic = |name: &str| transpose_for_instrument(name).map(|t| t.chromatic);
+        assert_eq!(chromatic("Trumpet in Bb"), Some(-2));
+        assert_eq!(chromatic("B\u{266d} Trumpet"), Some(-2));
+        assert_eq!(chromatic("Bb Tpt. 1"), Some(-2));
+        assert_eq!(chromatic("Trumpet in B-flat"), Some(-2));
+        assert_eq!(chromatic("Trumpet in C"), Some(0));
+        assert_eq!(chromatic("Trumpet in Eb"), Some(3));
+        assert_eq!(chromatic("Horn in F"), Some(-7));
+        assert_eq!(chromatic("Flugelhorn"), Some(-2));
+        assert_eq!(chromatic("Trumpet"), None);
+        assert_eq!(chromatic("Violin"), None);
+    }
 }
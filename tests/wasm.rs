@@ -0,0 +1,48 @@
+//! Tests for the WASM exports, run in Node with `wasm-pack test --node`.
+#![cfg(target_arch = "wasm32")]
+
+use js_sys::{Array, Reflect};
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::*;
+
+fn field(value: &JsValue, name: &str) -> JsValue {
+    Reflect::get(value, &JsValue::from_str(name)).unwrap()
+}
+
+#[wasm_bindgen_test]
+fn generate_exercise_returns_notes() {
+    let score = trumpet_rs::generate_exercise("major_scale", "C4", 120.0, None, None, None).unwrap();
+    let notes = Array::from(&field(&score, "notes"));
+    assert!(notes.length() > 0);
+    assert_eq!(field(&score, "tempo").as_f64(), Some(120.0));
+}
+
+#[wasm_bindgen_test]
+fn generate_exercise_rejects_unknown_type() {
+    assert!(trumpet_rs::generate_exercise("nonexistent", "C4", 120.0, None, None, None).is_err());
+}
+
+#[wasm_bindgen_test]
+fn curriculum_has_four_stages() {
+    let curriculum = Array::from(&trumpet_rs::get_curriculum().unwrap());
+    assert_eq!(curriculum.length(), 4);
+    let exercises = Array::from(&field(&curriculum.get(0), "exercises"));
+    assert!(exercises.length() > 0);
+}
+
+#[wasm_bindgen_test]
+fn parse_musicxml_returns_full_score() {
+    let xml = r#"<score-partwise version="3.1"><part id="P1"><measure number="1">
+        <attributes><divisions>1</divisions><key><fifths>-2</fifths></key>
+        <time><beats>4</beats><beat-type>4</beat-type></time></attributes>
+        <note><pitch><step>B</step><alter>-1</alter><octave>4</octave></pitch>
+        <duration>4</duration><type>whole</type></note>
+    </measure></part></score-partwise>"#;
+    let score = trumpet_rs::parse_musicxml(xml).unwrap();
+    assert_eq!(field(&score, "key_fifths").as_f64(), Some(-2.0));
+    let measures = Array::from(&field(&score, "measures"));
+    assert_eq!(measures.length(), 1);
+    assert_eq!(field(&measures.get(0), "duration_beats").as_f64(), Some(4.0));
+    let notes = Array::from(&field(&score, "notes"));
+    assert_eq!(field(&notes.get(0), "midi").as_f64(), Some(70.0));
+}
@@ -399,8 +399,12 @@ function showResults() {
     const techniqueHtml = renderTechniqueMetrics(analysis);
 
     let techniqueFeedbackHtml = "";
-    if (analysis.technique_feedback && analysis.technique_feedback.length > 0) {
-      techniqueFeedbackHtml = analysis.technique_feedback.map((msg) => `<p>${msg}</p>`).join("");
+    const techniqueMessages = [
+      ...(analysis.technique_feedback || []),
+      ...(analysis.articulation_feedback || []),
+    ];
+    if (techniqueMessages.length > 0) {
+      techniqueFeedbackHtml = techniqueMessages.map((msg) => `<p>${msg}</p>`).join("");
     }
 
     let html = `<div class="results-card">